@@ -7,7 +7,7 @@ use std::path::PathBuf;
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
-use crate::oauth::{TokenData, UserInfo};
+use crate::oauth::{TokenData, TokenExpiry, UserInfo};
 
 /// Account structure
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -18,11 +18,27 @@ pub struct Account {
     pub picture: Option<String>,
     pub refresh_token: String,
     pub access_token: Option<String>,
-    pub expires_at: Option<i64>,
+    pub expires_at: Option<TokenExpiry>,
     pub is_active: bool,
+    /// Named IDE targets this account is active in (`is_active` covers the default one)
+    #[serde(default)]
+    pub active_targets: Vec<String>,
     pub added_at: i64,
     pub last_switched: Option<i64>,
     pub last_checked: Option<i64>,
+    /// Set when Google rejects the refresh token (revoked or expired grant)
+    #[serde(default)]
+    pub auth_revoked: bool,
+}
+
+impl Account {
+    /// Check whether this account is active in the default IDE or in a named target
+    pub fn is_active_in(&self, target: Option<&str>) -> bool {
+        match target {
+            None => self.is_active,
+            Some(name) => self.active_targets.iter().any(|t| t == name),
+        }
+    }
 }
 
 /// Account manager handles all account operations
@@ -88,11 +104,13 @@ impl AccountManager {
             picture: None,
             refresh_token,
             access_token: token_data.as_ref().map(|t| t.access_token.clone()),
-            expires_at: token_data.map(|t| chrono::Utc::now().timestamp_millis() + (t.expires_in * 1000)),
+            expires_at: token_data.map(|t| t.expires_at),
             is_active: accounts.is_empty(), // First account is active
+            active_targets: Vec::new(),
             added_at: chrono::Utc::now().timestamp_millis(),
             last_switched: None,
             last_checked: None,
+            auth_revoked: false,
         };
         
         accounts.push(account.clone());
@@ -128,18 +146,32 @@ impl AccountManager {
         Ok(accounts.into_iter().find(|a| a.is_active))
     }
     
-    /// Set an account as active
-    pub fn set_active_account(&mut self, account_id: &str) -> anyhow::Result<()> {
+    /// Get the account active in the default IDE or in a named target
+    pub fn get_active_account_in(&self, target: Option<&str>) -> anyhow::Result<Option<Account>> {
+        let accounts = self.load_accounts()?;
+        Ok(accounts.into_iter().find(|a| a.is_active_in(target)))
+    }
+    
+    /// Set an account as active in the default IDE or in a named target
+    pub fn set_active_account(&mut self, account_id: &str, target: Option<&str>) -> anyhow::Result<()> {
         let mut accounts = self.load_accounts()?;
         
         let mut found = false;
         for account in &mut accounts {
-            if account.id == account_id {
-                account.is_active = true;
+            let selected = account.id == account_id;
+            if selected {
                 account.last_switched = Some(chrono::Utc::now().timestamp_millis());
                 found = true;
-            } else {
-                account.is_active = false;
+            }
+            
+            match target {
+                None => account.is_active = selected,
+                Some(name) => {
+                    account.active_targets.retain(|t| t != name);
+                    if selected {
+                        account.active_targets.push(name.to_string());
+                    }
+                }
             }
         }
         
@@ -156,14 +188,29 @@ impl AccountManager {
         &mut self,
         account_id: &str,
         access_token: &str,
-        expires_in: i64,
+        expires_at: TokenExpiry,
     ) -> anyhow::Result<()> {
         let mut accounts = self.load_accounts()?;
         
         for account in &mut accounts {
             if account.id == account_id {
                 account.access_token = Some(access_token.to_string());
-                account.expires_at = Some(chrono::Utc::now().timestamp_millis() + (expires_in * 1000));
+                account.expires_at = Some(expires_at);
+                break;
+            }
+        }
+        
+        self.save_accounts(&accounts)?;
+        Ok(())
+    }
+    
+    /// Record whether an account's refresh token has been revoked
+    pub fn set_auth_revoked(&mut self, account_id: &str, revoked: bool) -> anyhow::Result<()> {
+        let mut accounts = self.load_accounts()?;
+        
+        for account in &mut accounts {
+            if account.id == account_id {
+                account.auth_revoked = revoked;
                 break;
             }
         }
@@ -185,9 +232,10 @@ impl AccountManager {
             // Update existing
             existing.refresh_token = tokens.refresh_token;
             existing.access_token = Some(tokens.access_token);
-            existing.expires_at = Some(chrono::Utc::now().timestamp_millis() + (tokens.expires_in * 1000));
+            existing.expires_at = Some(tokens.expires_at);
             existing.name = user_info.name.or_else(|| Some(user_info.email.split('@').next().unwrap_or("Unknown").to_string()));
             existing.picture = user_info.picture;
+            existing.auth_revoked = false;
             
             let account = existing.clone();
             self.save_accounts(&accounts)?;
@@ -202,11 +250,13 @@ impl AccountManager {
             picture: user_info.picture,
             refresh_token: tokens.refresh_token,
             access_token: Some(tokens.access_token),
-            expires_at: Some(chrono::Utc::now().timestamp_millis() + (tokens.expires_in * 1000)),
+            expires_at: Some(tokens.expires_at),
             is_active: accounts.is_empty(),
+            active_targets: Vec::new(),
             added_at: chrono::Utc::now().timestamp_millis(),
             last_switched: None,
             last_checked: None,
+            auth_revoked: false,
         };
         
         accounts.push(account.clone());
@@ -0,0 +1,192 @@
+// Audit log - append-only history of switches and account changes
+
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::Instant;
+
+use serde::{Deserialize, Serialize};
+
+use crate::account;
+
+/// Serializes appends so concurrent events never interleave their lines
+static WRITE_LOCK: Mutex<()> = Mutex::new(());
+
+/// What happened
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AuditEvent {
+    Switch,
+    AutoSwitch,
+    /// Undo of the last switch
+    Revert,
+    Add,
+    Delete,
+    Import,
+    Export,
+    TokenRefreshFailed,
+}
+
+/// What started the event
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Trigger {
+    #[default]
+    Ui,
+    Cli,
+    Tray,
+    /// A global keyboard shortcut
+    Shortcut,
+    /// Background tasks: auto-switch, quota reset checks
+    Auto,
+}
+
+/// How the event ended
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Outcome {
+    Success,
+    Failure,
+}
+
+/// One line of the audit log
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuditEntry {
+    pub timestamp: i64,
+    pub event: AuditEvent,
+    pub account_email: Option<String>,
+    pub trigger: Trigger,
+    pub duration_ms: Option<u64>,
+    pub outcome: Outcome,
+    /// Stable machine-readable reason for a failure, e.g. "token_refresh_failed"
+    pub error_code: Option<String>,
+    /// Human-readable context: the error message, import counts, switch target
+    pub detail: Option<String>,
+}
+
+impl AuditEntry {
+    /// Start an entry stamped with the current time
+    pub fn new(event: AuditEvent, trigger: Trigger, outcome: Outcome) -> Self {
+        AuditEntry {
+            timestamp: chrono::Utc::now().timestamp_millis(),
+            event,
+            account_email: None,
+            trigger,
+            duration_ms: None,
+            outcome,
+            error_code: None,
+            detail: None,
+        }
+    }
+
+    pub fn email(mut self, email: impl Into<String>) -> Self {
+        self.account_email = Some(email.into());
+        self
+    }
+
+    /// Record how long the event took since `started`
+    pub fn duration(mut self, started: Instant) -> Self {
+        self.duration_ms = Some(started.elapsed().as_millis() as u64);
+        self
+    }
+
+    pub fn error_code(mut self, code: impl Into<String>) -> Self {
+        self.error_code = Some(code.into());
+        self
+    }
+
+    pub fn detail(mut self, detail: impl Into<String>) -> Self {
+        self.detail = Some(detail.into());
+        self
+    }
+}
+
+/// Filter for reading the history
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct HistoryQuery {
+    #[serde(default)]
+    pub event: Option<AuditEvent>,
+    #[serde(default)]
+    pub account_email: Option<String>,
+    /// Only entries at or after this time (ms)
+    #[serde(default)]
+    pub since: Option<i64>,
+    /// Maximum number of entries, newest first
+    #[serde(default)]
+    pub limit: Option<usize>,
+}
+
+impl HistoryQuery {
+    fn matches(&self, entry: &AuditEntry) -> bool {
+        self.event.map(|event| entry.event == event).unwrap_or(true)
+            && self
+                .account_email
+                .as_deref()
+                .map(|email| entry.account_email.as_deref() == Some(email))
+                .unwrap_or(true)
+            && self.since.map(|since| entry.timestamp >= since).unwrap_or(true)
+    }
+}
+
+/// Get the audit log file path
+pub fn get_audit_path() -> anyhow::Result<PathBuf> {
+    Ok(account::get_data_dir()?.join("audit.jsonl"))
+}
+
+/// Append an entry to the audit log
+///
+/// Logging never fails the operation being logged, so errors are only printed.
+pub fn record(entry: AuditEntry) {
+    if let Err(e) = append(&entry) {
+        eprintln!("Warning: Failed to write audit log: {}", e);
+    }
+}
+
+fn append(entry: &AuditEntry) -> anyhow::Result<()> {
+    let path = get_audit_path()?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    let mut line = serde_json::to_string(entry)?;
+    line.push('\n');
+
+    let _guard = WRITE_LOCK.lock().unwrap();
+    let mut file = OpenOptions::new().create(true).append(true).open(&path)?;
+    file.write_all(line.as_bytes())?;
+    Ok(())
+}
+
+/// Read the entries matching a query, newest first
+///
+/// Lines that do not parse (e.g. a write cut short by a crash) are skipped.
+pub fn load_history(query: &HistoryQuery) -> anyhow::Result<Vec<AuditEntry>> {
+    let path = get_audit_path()?;
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+
+    let content = fs::read_to_string(&path)?;
+    let entries = content
+        .lines()
+        .rev()
+        .filter_map(|line| serde_json::from_str::<AuditEntry>(line).ok())
+        .filter(|entry| query.matches(entry))
+        .take(query.limit.unwrap_or(usize::MAX))
+        .collect();
+    Ok(entries)
+}
+
+/// Export the entries matching a query as JSONL, oldest first
+pub fn export_history(query: &HistoryQuery) -> anyhow::Result<String> {
+    let mut entries = load_history(query)?;
+    entries.reverse();
+
+    let mut jsonl = String::new();
+    for entry in &entries {
+        jsonl.push_str(&serde_json::to_string(entry)?);
+        jsonl.push('\n');
+    }
+    Ok(jsonl)
+}
@@ -0,0 +1,136 @@
+// Auto-switch - rotate away from the active account when its quota runs low
+
+use std::collections::HashMap;
+
+use chrono::{Local, Timelike};
+use tauri::Manager;
+
+use crate::audit::Trigger;
+use crate::notify::NotificationCategory;
+use crate::recommend;
+use crate::settings::{self, AutoSwitchPolicy};
+use crate::AppState;
+
+/// Start the background task that enforces the auto-switch policy
+pub fn spawn(app_handle: tauri::AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        // When a switch to each account last failed, in ms since epoch
+        let mut failures = HashMap::new();
+        loop {
+            let policy = settings::load_settings()
+                .map(|s| s.auto_switch)
+                .unwrap_or_default();
+
+            if policy.enabled {
+                if let Err(e) = check_and_switch(&app_handle, &policy, &mut failures).await {
+                    eprintln!("Warning: Auto-switch check failed: {}", e);
+                }
+            }
+
+            let interval = policy.check_interval_minutes.max(1) as u64 * 60;
+            tokio::time::sleep(tokio::time::Duration::from_secs(interval)).await;
+        }
+    });
+}
+
+/// Check the active account and switch if the policy says so
+///
+/// Switches go to the tray target from settings. An account a switch failed
+/// for is skipped for one extra check interval, so a broken account is not
+/// retried on every check.
+async fn check_and_switch(
+    app_handle: &tauri::AppHandle,
+    policy: &AutoSwitchPolicy,
+    failures: &mut HashMap<String, i64>,
+) -> anyhow::Result<()> {
+    if let Some(quiet_hours) = &policy.quiet_hours {
+        if quiet_hours.contains(Local::now().hour()) {
+            return Ok(());
+        }
+    }
+
+    let state = app_handle.state::<AppState>();
+    let accounts = {
+        let manager = state.account_manager.lock().unwrap();
+        manager.load_accounts()?
+    };
+
+    let target = crate::tray::switch_target();
+    let active = match accounts.iter().find(|a| a.is_active_in(target.as_deref())) {
+        Some(active) => active.clone(),
+        None => return Ok(()),
+    };
+
+    // Respect the cooldown after any switch, manual or automatic
+    let now = chrono::Utc::now().timestamp_millis();
+    let cooldown_ms = policy.cooldown_minutes as i64 * 60 * 1000;
+    if let Some(last_switched) = active.last_switched {
+        if now - last_switched < cooldown_ms {
+            return Ok(());
+        }
+    }
+
+    let active_quota = crate::fetch_fresh_quota(&state, &active, Trigger::Auto).await?;
+    let active_percentage = match active_quota.find_model(&policy.model) {
+        Some(model) => model.percentage,
+        None => return Ok(()),
+    };
+
+    if active_percentage >= policy.threshold_percent {
+        return Ok(());
+    }
+
+    // Pick the account with the most headroom for the watched model
+    // Accounts whose last switch failed sit out until a second interval has passed
+    let backoff_ms = 2 * policy.check_interval_minutes.max(1) as i64 * 60 * 1000;
+    failures.retain(|_, failed_at| now - *failed_at < backoff_ms);
+    let others = accounts
+        .into_iter()
+        .filter(|a| a.id != active.id && !failures.contains_key(&a.id))
+        .collect();
+    let candidates = crate::collect_quotas(app_handle, others, Trigger::Auto).await;
+    let best = recommend::rank_accounts(&candidates, &policy.model)
+        .into_iter()
+        .find(|r| !r.auth_revoked && r.remaining_percentage.map(|p| p > active_percentage).unwrap_or(false));
+
+    let (account_id, email, percentage) = match best {
+        Some(best) => (best.account_id, best.email, best.remaining_percentage.unwrap_or_default()),
+        None => return Ok(()),
+    };
+
+    let options = crate::SwitchOptions {
+        target,
+        trigger: Trigger::Auto,
+        ..Default::default()
+    };
+    let response = crate::perform_switch(app_handle, &account_id, options).await;
+    if !matches!(&response, Ok(response) if response.success) {
+        failures.insert(account_id.clone(), now);
+    }
+    let response = response.map_err(|e| anyhow::anyhow!(e.error))?;
+
+    if response.success {
+        crate::tray::emit_accounts_updated(app_handle);
+    }
+
+    if policy.notify {
+        let body = if response.success {
+            format!(
+                "{} was at {}% for {}. Switched to {} ({}% remaining).",
+                active.email, active_percentage, policy.model, email, percentage
+            )
+        } else {
+            format!(
+                "Could not switch to {}: {}",
+                email,
+                response.error.unwrap_or_else(|| "Unknown error".to_string())
+            )
+        };
+
+        state
+            .notifier
+            .notify(NotificationCategory::SwitchResult, &account_id, "Antigravity Switch", &body);
+    }
+
+    Ok(())
+}
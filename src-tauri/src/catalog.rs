@@ -0,0 +1,132 @@
+// Model catalog - maps model ids to display names, families and priorities
+
+use std::fs;
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+
+use crate::account;
+
+/// Catalog shipped with the app
+const DEFAULT_CATALOG: &str = include_str!("../model_catalog.json");
+
+/// Priority used for models that match no catalog entry
+pub const FALLBACK_PRIORITY: i32 = 100;
+
+/// Model catalog
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModelCatalog {
+    pub version: u32,
+    pub models: Vec<ModelEntry>,
+}
+
+/// A catalog entry, matched against lowercase model ids
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModelEntry {
+    /// Substrings that must all appear in the model id
+    pub match_all: Vec<String>,
+    /// Substrings that must not appear in the model id
+    #[serde(default)]
+    pub match_none: Vec<String>,
+    /// Display name; the cleaned-up id is used when absent
+    pub display_name: Option<String>,
+    pub family: Option<String>,
+    pub priority: i32,
+    pub low_quota_threshold: Option<i32>,
+    pub color: Option<String>,
+}
+
+impl ModelEntry {
+    /// Check whether this entry applies to a lowercase model id
+    fn matches(&self, name_lower: &str) -> bool {
+        self.match_all.iter().all(|p| name_lower.contains(&p.to_lowercase()))
+            && !self.match_none.iter().any(|p| name_lower.contains(&p.to_lowercase()))
+    }
+}
+
+impl ModelCatalog {
+    /// Load the default catalog with the user's entries taking precedence
+    pub fn load() -> Self {
+        let mut catalog = Self::builtin();
+
+        match load_user_catalog() {
+            Ok(Some(user)) => {
+                let mut models = user.models;
+                models.extend(catalog.models);
+                catalog.models = models;
+                catalog.version = catalog.version.max(user.version);
+            }
+            Ok(None) => {}
+            Err(e) => eprintln!("Warning: Ignoring invalid model catalog: {}", e),
+        }
+
+        catalog
+    }
+
+    /// The catalog shipped with the app
+    pub fn builtin() -> Self {
+        serde_json::from_str(DEFAULT_CATALOG).expect("built-in model catalog is valid JSON")
+    }
+
+    /// Find the first entry matching a model id
+    pub fn lookup(&self, name: &str) -> Option<&ModelEntry> {
+        let name_lower = name.to_lowercase();
+        self.models.iter().find(|entry| entry.matches(&name_lower))
+    }
+}
+
+/// Get the user catalog file path
+pub fn get_user_catalog_path() -> anyhow::Result<PathBuf> {
+    Ok(account::get_data_dir()?.join("model_catalog.json"))
+}
+
+/// Load the user's catalog overrides, if any
+fn load_user_catalog() -> anyhow::Result<Option<ModelCatalog>> {
+    let path = get_user_catalog_path()?;
+    if !path.exists() {
+        return Ok(None);
+    }
+
+    let content = fs::read_to_string(&path)?;
+    Ok(Some(serde_json::from_str(&content)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builtin_catalog_parses() {
+        let catalog = ModelCatalog::builtin();
+        assert!(!catalog.models.is_empty());
+    }
+
+    #[test]
+    fn current_model_ids_map_to_display_names_and_priorities() {
+        let catalog = ModelCatalog::builtin();
+        let cases = [
+            ("claude-sonnet-4-5", "Claude Sonnet 4.5", 1),
+            ("claude-sonnet-4-5-thinking", "Claude Sonnet 4.5 (Thinking)", 2),
+            ("claude-opus-4-5-thinking", "Claude Opus 4.5 (Thinking)", 2),
+            ("claude-opus-4-5", "Claude Opus 4.5", 3),
+            ("gemini-3-pro-high", "Gemini 3 Pro (High)", 4),
+            ("gemini-3-pro-low", "Gemini 3 Pro (Low)", 4),
+            ("gemini-3-pro-preview", "Gemini 3 Pro", 4),
+            ("gemini-3-flash", "Gemini 3 Flash", 5),
+            ("gpt-oss-120b-medium", "GPT-OSS 120B", 6),
+        ];
+
+        for (id, display_name, priority) in cases {
+            let entry = catalog.lookup(id).unwrap_or_else(|| panic!("{} has no catalog entry", id));
+            assert_eq!(entry.display_name.as_deref(), Some(display_name), "{}", id);
+            assert_eq!(entry.priority, priority, "{}", id);
+        }
+    }
+
+    #[test]
+    fn lookup_ignores_case() {
+        let catalog = ModelCatalog::builtin();
+        let entry = catalog.lookup("Claude-Sonnet-4-5").unwrap();
+        assert_eq!(entry.display_name.as_deref(), Some("Claude Sonnet 4.5"));
+    }
+}
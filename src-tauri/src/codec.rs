@@ -0,0 +1,298 @@
+// Value codecs for the IDE database - base64 and the protobuf OAuth token blob
+
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use serde::Serialize;
+
+use crate::oauth::TokenExpiry;
+
+/// Encode bytes as standard padded base64
+pub fn encode_base64(data: &[u8]) -> String {
+    STANDARD.encode(data)
+}
+
+/// Decode standard base64, ignoring surrounding whitespace
+pub fn decode_base64(text: &str) -> anyhow::Result<Vec<u8>> {
+    Ok(STANDARD.decode(text.trim())?)
+}
+
+/// Encode the OAuth token blob as the base64 text stored in the database
+pub fn encode_oauth_value(access_token: &str, refresh_token: &str, expiry: TokenExpiry) -> String {
+    encode_base64(&create_oauth_field(access_token, refresh_token, expiry))
+}
+
+/// Decode the base64 OAuth token blob read from the database
+pub fn decode_oauth_value(text: &str) -> anyhow::Result<OAuthFields> {
+    decode_oauth_field(&decode_base64(text)?)
+}
+
+/// Create OAuth field in protobuf-like format
+pub fn create_oauth_field(access_token: &str, refresh_token: &str, expiry: TokenExpiry) -> Vec<u8> {
+    let mut parts = Vec::new();
+
+    // Field 1: access_token
+    add_string_field(&mut parts, 1, access_token);
+
+    // Field 2: token_type (Bearer)
+    add_string_field(&mut parts, 2, "Bearer");
+
+    // Field 3: refresh_token
+    add_string_field(&mut parts, 3, refresh_token);
+
+    // Field 4: expiry as a google.protobuf.Timestamp
+    let (seconds, nanos) = expiry.timestamp();
+    let mut timestamp_parts = Vec::new();
+    add_varint(&mut timestamp_parts, (1 << 3) as u64); // Field 1 (seconds), wire type 0
+    add_varint(&mut timestamp_parts, seconds as u64);
+    add_varint(&mut timestamp_parts, (2 << 3) as u64); // Field 2 (nanos), wire type 0
+    add_varint(&mut timestamp_parts, nanos as u64);
+
+    add_varint(&mut parts, ((4 << 3) | 2) as u64); // Field 4, wire type 2 (length-delimited)
+    add_varint(&mut parts, timestamp_parts.len() as u64);
+    parts.extend_from_slice(&timestamp_parts);
+
+    // Wrap in field 6
+    let mut result = Vec::new();
+    add_varint(&mut result, ((6 << 3) | 2) as u64); // Field 6, wire type 2
+    add_varint(&mut result, parts.len() as u64);
+    result.extend_from_slice(&parts);
+
+    result
+}
+
+/// Add a string field to protobuf
+fn add_string_field(parts: &mut Vec<u8>, field_num: u32, value: &str) {
+    add_varint(parts, ((field_num << 3) | 2) as u64); // Wire type 2 = length-delimited
+    add_varint(parts, value.len() as u64);
+    parts.extend_from_slice(value.as_bytes());
+}
+
+/// Encode a value as varint
+fn add_varint(parts: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        parts.push(((value & 0x7F) | 0x80) as u8);
+        value >>= 7;
+    }
+    parts.push(value as u8);
+}
+
+/// Fields of the OAuth token blob
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct OAuthFields {
+    pub access_token: String,
+    pub token_type: String,
+    pub refresh_token: String,
+    pub expiry: Option<TokenExpiry>,
+}
+
+/// Decode the field-6 OAuth wrapper, as written by `create_oauth_field` or the IDE
+///
+/// Fields other than the ones read here are skipped by wire type, wherever
+/// they appear, so blobs with extra fields still decode.
+pub fn decode_oauth_field(data: &[u8]) -> anyhow::Result<OAuthFields> {
+    let mut inner = None;
+    let mut outer = data;
+    while !outer.is_empty() {
+        if let (6, FieldValue::Bytes(value)) = read_field(&mut outer)? {
+            inner = Some(value);
+        }
+    }
+    let mut inner = inner.ok_or_else(|| anyhow::anyhow!("OAuth field 6 not found"))?;
+
+    let mut fields = OAuthFields::default();
+    while !inner.is_empty() {
+        match read_field(&mut inner)? {
+            (1, FieldValue::Bytes(value)) => {
+                fields.access_token = String::from_utf8_lossy(value).to_string()
+            }
+            (2, FieldValue::Bytes(value)) => {
+                fields.token_type = String::from_utf8_lossy(value).to_string()
+            }
+            (3, FieldValue::Bytes(value)) => {
+                fields.refresh_token = String::from_utf8_lossy(value).to_string()
+            }
+            (4, FieldValue::Bytes(value)) => fields.expiry = decode_timestamp(value)?,
+            _ => {}
+        }
+    }
+
+    Ok(fields)
+}
+
+/// Decode a google.protobuf.Timestamp message
+fn decode_timestamp(mut data: &[u8]) -> anyhow::Result<Option<TokenExpiry>> {
+    let (mut seconds, mut nanos) = (0, 0);
+    while !data.is_empty() {
+        match read_field(&mut data)? {
+            (1, FieldValue::Varint(value)) => seconds = value as i64,
+            (2, FieldValue::Varint(value)) => nanos = value as u32,
+            _ => {}
+        }
+    }
+    Ok(TokenExpiry::from_timestamp(seconds, nanos))
+}
+
+/// Payload of one protobuf field
+enum FieldValue<'a> {
+    Varint(u64),
+    Bytes(&'a [u8]),
+    /// A fixed32 or fixed64 value, which none of the read fields use
+    Fixed,
+}
+
+/// Read one field of any wire type, returning its number and payload
+fn read_field<'a>(data: &mut &'a [u8]) -> anyhow::Result<(u64, FieldValue<'a>)> {
+    let tag = read_varint(data)?;
+    let field = tag >> 3;
+    let value = match tag & 0x07 {
+        0 => FieldValue::Varint(read_varint(data)?),
+        1 => {
+            skip(data, 8, field)?;
+            FieldValue::Fixed
+        }
+        2 => {
+            let len = read_varint(data)?;
+            FieldValue::Bytes(skip(data, len, field)?)
+        }
+        5 => {
+            skip(data, 4, field)?;
+            FieldValue::Fixed
+        }
+        wire_type => anyhow::bail!("Unsupported wire type {} for field {}", wire_type, field),
+    };
+    Ok((field, value))
+}
+
+/// Take `len` bytes off the front of the slice
+fn skip<'a>(data: &mut &'a [u8], len: u64, field: u64) -> anyhow::Result<&'a [u8]> {
+    if len > data.len() as u64 {
+        anyhow::bail!("Field {} is truncated", field);
+    }
+    let (value, rest) = data.split_at(len as usize);
+    *data = rest;
+    Ok(value)
+}
+
+/// Read a varint, advancing the slice past it
+fn read_varint(data: &mut &[u8]) -> anyhow::Result<u64> {
+    let mut value = 0u64;
+    for (i, byte) in data.iter().enumerate().take(10) {
+        value |= ((byte & 0x7F) as u64) << (7 * i);
+        if byte & 0x80 == 0 {
+            *data = &data[i + 1..];
+            return Ok(value);
+        }
+    }
+    anyhow::bail!("Invalid varint")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Small xorshift generator so the cases are random but reproducible
+    struct Rng(u64);
+
+    impl Rng {
+        fn next(&mut self) -> u64 {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            self.0
+        }
+
+        fn below(&mut self, n: u64) -> u64 {
+            self.next() % n
+        }
+
+        fn token(&mut self) -> String {
+            let len = self.below(300) as usize;
+            (0..len)
+                .map(|_| match self.below(20) {
+                    0 => 'é',
+                    1 => '/',
+                    _ => (b'!' + self.below(94) as u8) as char,
+                })
+                .collect()
+        }
+
+        fn bytes(&mut self, len: usize) -> Vec<u8> {
+            (0..len).map(|_| self.next() as u8).collect()
+        }
+    }
+
+    #[test]
+    fn oauth_value_round_trips() {
+        let mut rng = Rng(0x9E37_79B9_7F4A_7C15);
+        for _ in 0..2000 {
+            let access_token = rng.token();
+            let refresh_token = rng.token();
+            let expiry = TokenExpiry::from_timestamp(
+                rng.below(8_000_000_000) as i64,
+                rng.below(1_000_000_000) as u32,
+            )
+            .unwrap();
+
+            let decoded =
+                decode_oauth_value(&encode_oauth_value(&access_token, &refresh_token, expiry))
+                    .unwrap();
+            assert_eq!(decoded.access_token, access_token);
+            assert_eq!(decoded.token_type, "Bearer");
+            assert_eq!(decoded.refresh_token, refresh_token);
+            assert_eq!(decoded.expiry, Some(expiry));
+        }
+    }
+
+    #[test]
+    fn base64_round_trips() {
+        let mut rng = Rng(42);
+        for len in 0..300 {
+            let data = rng.bytes(len);
+            assert_eq!(decode_base64(&encode_base64(&data)).unwrap(), data);
+        }
+        assert_eq!(decode_base64("  aGVsbG8=\n").unwrap(), b"hello");
+        assert!(decode_base64("not base64!").is_err());
+    }
+
+    #[test]
+    fn decodes_unknown_fields_in_any_order() {
+        let expiry = TokenExpiry::from_timestamp(1_700_000_000, 5).unwrap();
+        let blob = create_oauth_field("access", "refresh", expiry);
+
+        let mut data = Vec::new();
+        add_varint(&mut data, 1 << 3); // field 1, varint
+        add_varint(&mut data, 300);
+        add_varint(&mut data, (2 << 3) | 1); // field 2, fixed64
+        data.extend_from_slice(&[0; 8]);
+        add_string_field(&mut data, 3, "other");
+        data.extend_from_slice(&blob);
+        add_varint(&mut data, (7 << 3) | 5); // field 7, fixed32
+        data.extend_from_slice(&[0; 4]);
+
+        let fields = decode_oauth_field(&data).unwrap();
+        assert_eq!(fields.access_token, "access");
+        assert_eq!(fields.refresh_token, "refresh");
+        assert_eq!(fields.expiry, Some(expiry));
+    }
+
+    #[test]
+    fn rejects_truncated_and_garbage_input() {
+        let expiry = TokenExpiry::from_timestamp(1_700_000_000, 0).unwrap();
+        let blob = create_oauth_field("access-token", "refresh-token", expiry);
+        for len in 0..blob.len() {
+            assert!(
+                decode_oauth_field(&blob[..len]).is_err(),
+                "prefix of {} bytes decoded",
+                len
+            );
+        }
+
+        let mut rng = Rng(7);
+        for _ in 0..5000 {
+            let len = rng.below(64) as usize;
+            let _ = decode_oauth_field(&rng.bytes(len));
+        }
+        assert!(decode_oauth_field(&[0xFF; 12]).is_err());
+        assert!(decode_oauth_value("%%%").is_err());
+    }
+}
@@ -2,8 +2,9 @@
 
 #![allow(dead_code)]
 
+use std::io::Write;
 use std::path::PathBuf;
-use std::process::Command;
+use std::process::{Command, Stdio};
 
 /// Check if sqlite3 CLI is available
 pub fn check_sqlite3() -> bool {
@@ -14,6 +15,24 @@ pub fn check_sqlite3() -> bool {
         .unwrap_or(false)
 }
 
+/// Get the version of the sqlite3 CLI, if it is installed
+pub fn sqlite3_version() -> Option<String> {
+    let output = Command::new("sqlite3").arg("--version").output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    
+    String::from_utf8_lossy(&output.stdout)
+        .split_whitespace()
+        .next()
+        .map(|v| v.to_string())
+}
+
+/// Quote a string as an SQL literal
+pub fn quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "''"))
+}
+
 /// Execute a SQL query on the database
 pub fn execute_query(db_path: &PathBuf, query: &str) -> anyhow::Result<String> {
     let output = Command::new("sqlite3")
@@ -28,6 +47,31 @@ pub fn execute_query(db_path: &PathBuf, query: &str) -> anyhow::Result<String> {
     Ok(String::from_utf8_lossy(&output.stdout).to_string())
 }
 
+/// Execute SQL statements on the database, stopping at the first error
+///
+/// The script is passed on stdin so large values are not limited by the
+/// maximum command-line argument length.
+pub fn execute_script(db_path: &PathBuf, script: &str) -> anyhow::Result<String> {
+    let mut child = Command::new("sqlite3")
+        .arg("-bail")
+        .arg(db_path)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+    
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(script.as_bytes())?;
+    }
+    let output = child.wait_with_output()?;
+    
+    if !output.status.success() {
+        anyhow::bail!("SQLite script failed: {}", String::from_utf8_lossy(&output.stderr));
+    }
+    
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
 /// Check if a table exists
 pub fn table_exists(db_path: &PathBuf, table_name: &str) -> anyhow::Result<bool> {
     let result = execute_query(
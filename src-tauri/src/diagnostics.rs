@@ -0,0 +1,78 @@
+// Diagnostics - environment and IDE compatibility report
+
+use std::path::PathBuf;
+
+use serde::Serialize;
+
+use crate::account;
+use crate::db;
+use crate::install::{self, Installation};
+use crate::recipe::SwitchRecipes;
+use crate::settings;
+use crate::switch::{self, VersionCheck};
+
+/// Everything needed to tell why a switch does or does not work on a machine
+#[derive(Debug, Clone, Serialize)]
+pub struct Diagnostics {
+    pub app_version: String,
+    pub os: String,
+    pub arch: String,
+    pub data_dir: Option<PathBuf>,
+    /// Version of the sqlite3 CLI used to edit the IDE database, if installed
+    pub sqlite3_version: Option<String>,
+    /// Id of the installation switches are applied to by default
+    pub selected: Option<String>,
+    /// Detected installations followed by the named IDE targets
+    pub installations: Vec<InstallationDiagnostics>,
+}
+
+/// Compatibility of one installation or named target
+#[derive(Debug, Clone, Serialize)]
+pub struct InstallationDiagnostics {
+    pub installation: Installation,
+    pub running: bool,
+    /// Name of the switch recipe that would be used, if any applies
+    pub recipe: Option<String>,
+    pub version_check: VersionCheck,
+}
+
+/// Collect the diagnostics report
+///
+/// Installations whose version is not in the app's metadata files are asked
+/// with `<binary> --version`, which only happens here.
+pub async fn collect() -> Diagnostics {
+    let recipes = SwitchRecipes::load();
+
+    let mut installations = install::detect_installations();
+    let targets = settings::load_settings().map(|s| s.targets).unwrap_or_default();
+    for target in &targets {
+        match install::resolve_target(Some(&target.name)) {
+            Ok(installation) => installations.push(installation),
+            Err(e) => eprintln!("Warning: Cannot resolve IDE target {}: {}", target.name, e),
+        }
+    }
+    for installation in &mut installations {
+        install::probe_command_version(installation).await;
+    }
+
+    Diagnostics {
+        app_version: env!("CARGO_PKG_VERSION").to_string(),
+        os: std::env::consts::OS.to_string(),
+        arch: std::env::consts::ARCH.to_string(),
+        data_dir: account::get_data_dir().ok(),
+        sqlite3_version: db::sqlite3_version(),
+        selected: install::selected_installation().ok().map(|i| i.id),
+        installations: installations
+            .into_iter()
+            .map(|installation| {
+                let recipe = recipes.select(installation.version.as_deref());
+                InstallationDiagnostics {
+                    running: switch::is_target_running(&installation),
+                    recipe: recipe.map(|r| r.name.clone()),
+                    version_check: switch::check_version(installation.version.as_deref(), recipe),
+                    installation,
+                }
+            })
+            .collect(),
+    }
+}
@@ -0,0 +1,455 @@
+// Antigravity installation discovery - user-data dirs and launch commands
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use crate::settings::{self, CustomInstallation, IdeTarget};
+
+/// How long `<binary> --version` may run before it is abandoned
+const VERSION_COMMAND_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Versions reported by `<binary> --version`, keyed by binary path
+static VERSION_COMMAND_CACHE: OnceLock<Mutex<HashMap<PathBuf, Option<String>>>> = OnceLock::new();
+
+/// How the IDE was installed
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum InstallKind {
+    Native,
+    Tarball,
+    AppImage,
+    Flatpak,
+    Snap,
+    Custom,
+}
+
+/// A detected (or user-configured) IDE installation
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Installation {
+    pub id: String,
+    pub kind: InstallKind,
+    pub label: String,
+    /// Directory holding `User/globalStorage/state.vscdb`
+    pub user_data_dir: PathBuf,
+    /// Program and arguments used to start the IDE; empty if none was found
+    pub launcher: Vec<String>,
+    pub db_exists: bool,
+    /// IDE version read from the installed app's product.json/package.json, or
+    /// from `--version` once diagnostics has run it
+    pub version: Option<String>,
+    /// Name of the IDE target when this is a named profile rather than the default data dir
+    pub profile: Option<String>,
+}
+
+impl Installation {
+    fn new(id: String, kind: InstallKind, label: String, user_data_dir: PathBuf, launcher: Vec<String>) -> Self {
+        let db_exists = db_path_for(&user_data_dir).exists();
+        let version = version_from_resources(&launcher).or_else(|| cached_command_version(&launcher));
+        Installation {
+            id,
+            kind,
+            label,
+            user_data_dir,
+            launcher,
+            db_exists,
+            version,
+            profile: None,
+        }
+    }
+
+    /// Path of the IDE's state database
+    pub fn db_path(&self) -> PathBuf {
+        db_path_for(&self.user_data_dir)
+    }
+
+    /// The id reduced to characters that are safe in a directory name
+    pub fn slug(&self) -> String {
+        self.id
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+            .collect()
+    }
+}
+
+/// Path of the state database inside a user-data dir
+fn db_path_for(user_data_dir: &Path) -> PathBuf {
+    user_data_dir
+        .join("User")
+        .join("globalStorage")
+        .join("state.vscdb")
+}
+
+/// Read the IDE version from the app resources next to the launcher
+fn version_from_resources(launcher: &[String]) -> Option<String> {
+    app_resource_dirs(launcher).iter().find_map(|dir| {
+        ["product.json", "package.json"].iter().find_map(|file| {
+            let content = std::fs::read_to_string(dir.join(file)).ok()?;
+            let json: serde_json::Value = serde_json::from_str(&content).ok()?;
+            json.get("version")?.as_str().map(|v| v.to_string())
+        })
+    })
+}
+
+/// The binary a launcher runs directly, if `<binary> --version` can be asked
+///
+/// Wrapped launchers (`flatpak run`, `snap run`, `open -a`) are skipped since
+/// they start a sandbox or the app itself rather than print a version.
+fn version_program(launcher: &[String]) -> Option<PathBuf> {
+    match launcher {
+        [program] => Some(PathBuf::from(program)).filter(|p| p.is_file()),
+        _ => None,
+    }
+}
+
+/// A version `probe_command_version` found earlier, without running anything
+fn cached_command_version(launcher: &[String]) -> Option<String> {
+    let program = version_program(launcher)?;
+    let cache = VERSION_COMMAND_CACHE.get()?;
+    let version = cache.lock().unwrap().get(&program).cloned();
+    version.flatten()
+}
+
+/// Fill in a missing version by asking the IDE binary, caching the answer
+///
+/// This starts the IDE binary, so it only runs on request (diagnostics) and
+/// never while installations are detected.
+pub async fn probe_command_version(installation: &mut Installation) {
+    if installation.version.is_some() {
+        return;
+    }
+    let program = match version_program(&installation.launcher) {
+        Some(program) => program,
+        None => return,
+    };
+
+    let cache = VERSION_COMMAND_CACHE.get_or_init(Default::default);
+    let cached = cache.lock().unwrap().get(&program).cloned();
+    let version = match cached {
+        Some(version) => version,
+        None => {
+            let version = run_version_command(&program).await;
+            cache.lock().unwrap().insert(program, version.clone());
+            version
+        }
+    };
+    installation.version = version;
+}
+
+/// Run `<program> --version` and return the first line that looks like a version
+async fn run_version_command(program: &Path) -> Option<String> {
+    let output = tokio::process::Command::new(program)
+        .arg("--version")
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .kill_on_drop(true)
+        .output();
+    let output = tokio::time::timeout(VERSION_COMMAND_TIMEOUT, output).await.ok()?.ok()?;
+
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .map(str::trim)
+        .find(|line| line.starts_with(|c: char| c.is_ascii_digit()))
+        .map(str::to_string)
+}
+
+/// Candidate `resources/app` directories of the installed app
+fn app_resource_dirs(launcher: &[String]) -> Vec<PathBuf> {
+    #[cfg(target_os = "macos")]
+    {
+        let _ = launcher;
+        let mut apps = vec![PathBuf::from("/Applications/Antigravity.app")];
+        if let Some(home_dir) = dirs::home_dir() {
+            apps.push(home_dir.join("Applications").join("Antigravity.app"));
+        }
+        apps.into_iter()
+            .map(|app| app.join("Contents").join("Resources").join("app"))
+            .collect()
+    }
+
+    #[cfg(not(target_os = "macos"))]
+    {
+        // Launchers are often symlinks or scripts in a bin/ dir next to the binary
+        let binary = match launcher.first().and_then(|program| std::fs::canonicalize(program).ok()) {
+            Some(binary) => binary,
+            None => return Vec::new(),
+        };
+        binary
+            .ancestors()
+            .skip(1)
+            .take(2)
+            .map(|dir| dir.join("resources").join("app"))
+            .collect()
+    }
+}
+
+/// Detect installations, user-configured ones first
+pub fn detect_installations() -> Vec<Installation> {
+    let custom = settings::load_settings()
+        .map(|s| s.installation.custom)
+        .unwrap_or_default();
+
+    let mut installations: Vec<Installation> = custom.iter().map(custom_installation).collect();
+
+    installations.extend(detect_platform_installations());
+    installations
+}
+
+/// Get the installation selected in settings, or the best detected one
+pub fn selected_installation() -> anyhow::Result<Installation> {
+    let selected = settings::load_settings()
+        .map(|s| s.installation.selected)
+        .unwrap_or_default();
+    let installations = detect_installations();
+
+    if let Some(id) = selected {
+        match installations.iter().find(|i| i.id == id) {
+            Some(installation) => return Ok(installation.clone()),
+            None => eprintln!("Warning: Selected installation {} not found, using auto-detection", id),
+        }
+    }
+
+    // Prefer an installation whose database already exists
+    installations
+        .iter()
+        .find(|i| i.db_exists)
+        .or_else(|| installations.first())
+        .cloned()
+        .ok_or_else(|| anyhow::anyhow!("No Antigravity installation found"))
+}
+
+/// Resolve the IDE a switch applies to: a named target, or the selected installation
+pub fn resolve_target(name: Option<&str>) -> anyhow::Result<Installation> {
+    let name = match name {
+        Some(name) => name,
+        None => return selected_installation(),
+    };
+
+    let targets = settings::load_settings()?.targets;
+    let target = targets
+        .iter()
+        .find(|t| t.name == name)
+        .ok_or_else(|| anyhow::anyhow!("IDE target '{}' not found", name))?;
+
+    target_installation(target, &selected_installation()?)
+}
+
+/// Build the installation for a named target on top of a base installation
+fn target_installation(target: &IdeTarget, base: &Installation) -> anyhow::Result<Installation> {
+    // Without its own launcher a target reuses the base one with its data dir
+    let launcher = if target.launcher.is_empty() {
+        if base.launcher.is_empty() {
+            anyhow::bail!("No launcher found for IDE target '{}'", target.name);
+        }
+        let mut launcher = base.launcher.clone();
+        launcher.push(format!("--user-data-dir={}", target.user_data_dir.display()));
+        launcher
+    } else {
+        target.launcher.clone()
+    };
+
+    let mut installation = Installation::new(
+        format!("target:{}", target.name),
+        base.kind.clone(),
+        target.name.clone(),
+        target.user_data_dir.clone(),
+        launcher,
+    );
+    installation.profile = Some(target.name.clone());
+    Ok(installation)
+}
+
+/// Build an installation from a user-configured entry
+fn custom_installation(custom: &CustomInstallation) -> Installation {
+    Installation::new(
+        format!("custom:{}", custom.id),
+        InstallKind::Custom,
+        custom.label.clone(),
+        custom.user_data_dir.clone(),
+        custom.launcher.clone(),
+    )
+}
+
+#[cfg(target_os = "macos")]
+fn detect_platform_installations() -> Vec<Installation> {
+    let Some(home_dir) = dirs::home_dir() else {
+        return Vec::new();
+    };
+
+    vec![Installation::new(
+        "native".to_string(),
+        InstallKind::Native,
+        "Antigravity".to_string(),
+        home_dir.join("Library").join("Application Support").join("Antigravity"),
+        vec!["open".to_string(), "-a".to_string(), "Antigravity".to_string()],
+    )]
+}
+
+#[cfg(target_os = "windows")]
+fn detect_platform_installations() -> Vec<Installation> {
+    let Some(home_dir) = dirs::home_dir() else {
+        return Vec::new();
+    };
+
+    let candidates = [
+        home_dir
+            .join("AppData")
+            .join("Local")
+            .join("Programs")
+            .join("Antigravity")
+            .join("Antigravity.exe"),
+        PathBuf::from("C:\\Program Files\\Antigravity\\Antigravity.exe"),
+    ];
+    let launcher = candidates
+        .iter()
+        .find(|p| p.exists())
+        .map(|p| vec![p.to_string_lossy().to_string()])
+        .unwrap_or_default();
+
+    vec![Installation::new(
+        "native".to_string(),
+        InstallKind::Native,
+        "Antigravity".to_string(),
+        home_dir.join("AppData").join("Roaming").join("Antigravity"),
+        launcher,
+    )]
+}
+
+#[cfg(target_os = "linux")]
+fn detect_platform_installations() -> Vec<Installation> {
+    let Some(home_dir) = dirs::home_dir() else {
+        return Vec::new();
+    };
+
+    let mut installations = Vec::new();
+
+    // Native packages, tarballs and AppImages all share the XDG config dir
+    let config_home = std::env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .filter(|p| p.is_absolute())
+        .unwrap_or_else(|| home_dir.join(".config"));
+    let xdg_data_dir = config_home.join("Antigravity");
+
+    if let Some(binary) = find_on_path("antigravity") {
+        installations.push(Installation::new(
+            "native".to_string(),
+            InstallKind::Native,
+            format!("Antigravity ({})", binary.display()),
+            xdg_data_dir.clone(),
+            vec![binary.to_string_lossy().to_string()],
+        ));
+    }
+
+    let tarball_dirs = [
+        home_dir.join(".local").join("share").join("antigravity"),
+        home_dir.join("Applications").join("Antigravity"),
+        home_dir.join("antigravity"),
+        PathBuf::from("/opt/antigravity"),
+        PathBuf::from("/opt/Antigravity"),
+    ];
+    for dir in &tarball_dirs {
+        let binary = dir.join("antigravity");
+        if binary.is_file() {
+            installations.push(Installation::new(
+                format!("tarball:{}", dir.display()),
+                InstallKind::Tarball,
+                format!("Antigravity tarball ({})", dir.display()),
+                xdg_data_dir.clone(),
+                vec![binary.to_string_lossy().to_string()],
+            ));
+        }
+    }
+
+    for dir in [home_dir.join("Applications"), home_dir.join(".local").join("bin")] {
+        for appimage in find_appimages(&dir) {
+            installations.push(Installation::new(
+                format!("appimage:{}", appimage.display()),
+                InstallKind::AppImage,
+                format!("Antigravity AppImage ({})", appimage.display()),
+                xdg_data_dir.clone(),
+                vec![appimage.to_string_lossy().to_string()],
+            ));
+        }
+    }
+
+    // Flatpak keeps each app's config under ~/.var/app/<app-id>/config
+    if let Ok(entries) = std::fs::read_dir(home_dir.join(".var").join("app")) {
+        for entry in entries.flatten() {
+            let app_id = entry.file_name().to_string_lossy().to_string();
+            let data_dir = entry.path().join("config").join("Antigravity");
+            if data_dir.is_dir() {
+                installations.push(Installation::new(
+                    format!("flatpak:{}", app_id),
+                    InstallKind::Flatpak,
+                    format!("Antigravity Flatpak ({})", app_id),
+                    data_dir,
+                    vec!["flatpak".to_string(), "run".to_string(), app_id],
+                ));
+            }
+        }
+    }
+
+    // Snap keeps its config under ~/snap/<name>/current
+    let snap_data_dir = home_dir
+        .join("snap")
+        .join("antigravity")
+        .join("current")
+        .join(".config")
+        .join("Antigravity");
+    if snap_data_dir.is_dir() {
+        installations.push(Installation::new(
+            "snap".to_string(),
+            InstallKind::Snap,
+            "Antigravity Snap".to_string(),
+            snap_data_dir,
+            vec!["snap".to_string(), "run".to_string(), "antigravity".to_string()],
+        ));
+    }
+
+    // Fall back to the XDG dir so a database can still be found without a launcher
+    if !installations.iter().any(|i| i.user_data_dir == xdg_data_dir) && xdg_data_dir.is_dir() {
+        installations.push(Installation::new(
+            "xdg".to_string(),
+            InstallKind::Native,
+            "Antigravity (no launcher found)".to_string(),
+            xdg_data_dir,
+            Vec::new(),
+        ));
+    }
+
+    installations
+}
+
+/// Find an executable on PATH
+#[cfg(target_os = "linux")]
+fn find_on_path(name: &str) -> Option<PathBuf> {
+    let path = std::env::var_os("PATH")?;
+    std::env::split_paths(&path)
+        .map(|dir| dir.join(name))
+        .find(|candidate| candidate.is_file())
+}
+
+/// Find Antigravity AppImages in a directory
+#[cfg(target_os = "linux")]
+fn find_appimages(dir: &Path) -> Vec<PathBuf> {
+    std::fs::read_dir(dir)
+        .map(|entries| {
+            entries
+                .flatten()
+                .map(|e| e.path())
+                .filter(|p| {
+                    let name = p
+                        .file_name()
+                        .map(|n| n.to_string_lossy().to_lowercase())
+                        .unwrap_or_default();
+                    name.contains("antigravity") && name.ends_with(".appimage")
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
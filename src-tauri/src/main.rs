@@ -6,23 +6,72 @@
     windows_subsystem = "windows"
 )]
 
+use std::collections::HashMap;
+use std::path::PathBuf;
 use std::sync::Mutex;
 
 use serde::{Deserialize, Serialize};
 use tauri::{CustomMenuItem, Manager, State, SystemTray, SystemTrayEvent, SystemTrayMenu};
 
 mod account;
+mod audit;
+mod autoswitch;
+mod catalog;
+mod codec;
 mod db;
+mod diagnostics;
+mod install;
+mod notify;
 mod oauth;
+mod process;
 mod quota;
+mod recipe;
+mod recommend;
+mod scheduler;
+mod settings;
+mod shortcuts;
+mod snapshot;
 mod switch;
+mod tray;
 
 use account::{Account, AccountManager};
+use audit::{AuditEntry, AuditEvent, HistoryQuery, Outcome, Trigger};
+use catalog::ModelCatalog;
+use diagnostics::Diagnostics;
+use install::Installation;
+use notify::{NotificationCategory, Notifier};
+use oauth::TokenExpiry;
 use quota::QuotaInfo;
+use recipe::SwitchRecipes;
+use recommend::Recommendation;
+use scheduler::ResetScheduler;
+use settings::{
+    AutoSwitchPolicy, IdeTarget, InstallationSettings, NotificationSettings, QuotaThresholds, Settings,
+    ShortcutSettings, SwitchMode,
+};
 
 // Application state
 pub struct AppState {
     account_manager: Mutex<AccountManager>,
+    reset_scheduler: ResetScheduler,
+    /// Last fetched quota of each account, keyed by account id
+    quota_cache: Mutex<HashMap<String, QuotaInfo>>,
+    notifier: Notifier,
+    /// The most recent switch, while it can still be reverted
+    last_switch: Mutex<Option<LastSwitch>>,
+    /// Held while a switch or revert runs, so two never touch the IDE at once
+    switch_lock: tokio::sync::Mutex<()>,
+}
+
+/// What a switch replaced, so it can be reverted
+#[derive(Debug, Clone)]
+struct LastSwitch {
+    /// Named IDE target, `None` for the default IDE
+    target: Option<String>,
+    previous_account_id: String,
+    account_id: String,
+    /// Database backup taken right before the switch wrote to it
+    backup_path: PathBuf,
 }
 
 // Error types
@@ -82,6 +131,29 @@ struct SwitchResponse {
     success: bool,
     email: Option<String>,
     error: Option<String>,
+    /// Stable reason for a failure, as recorded in the audit log
+    error_code: Option<String>,
+    /// Whether the IDE was seen running again after the switch, if it was relaunched
+    ide_restarted: Option<bool>,
+    /// What the switch would do, for dry runs
+    plan: Option<switch::SwitchPlan>,
+    /// Caveat the user should see, such as an undetected IDE version
+    warning: Option<String>,
+}
+
+impl SwitchResponse {
+    /// A failed switch with its audit error code
+    fn failed(error_code: &str, error: impl Into<String>) -> Self {
+        SwitchResponse {
+            success: false,
+            email: None,
+            error: Some(error.into()),
+            error_code: Some(error_code.to_string()),
+            ide_restarted: None,
+            plan: None,
+            warning: None,
+        }
+    }
 }
 
 #[derive(Serialize)]
@@ -91,6 +163,21 @@ struct ImportResponse {
     updated: usize,
 }
 
+#[derive(Serialize)]
+struct RecommendResponse {
+    success: bool,
+    model: String,
+    recommendations: Vec<Recommendation>,
+}
+
+#[derive(Serialize)]
+struct InstallationsResponse {
+    success: bool,
+    installations: Vec<Installation>,
+    /// Id of the installation switches are applied to
+    active: Option<String>,
+}
+
 #[derive(Serialize, Deserialize, Clone)]
 struct AccountWithQuota {
     id: String,
@@ -99,6 +186,7 @@ struct AccountWithQuota {
     picture: Option<String>,
     quota: Option<QuotaInfo>,
     is_active: bool,
+    active_targets: Vec<String>,
     last_checked: Option<i64>,
 }
 
@@ -106,7 +194,7 @@ struct AccountWithQuota {
 
 /// Get all accounts with their quota information
 #[tauri::command]
-async fn get_accounts(state: State<'_, AppState>) -> Result<AccountsResponse, ApiError> {
+async fn get_accounts(state: State<'_, AppState>, app_handle: tauri::AppHandle) -> Result<AccountsResponse, ApiError> {
     // Load accounts first, then release the lock before await
     let accounts = {
         let manager = state.account_manager.lock().unwrap();
@@ -123,6 +211,10 @@ async fn get_accounts(state: State<'_, AppState>) -> Result<AccountsResponse, Ap
             None
         };
         
+        if let Some(quota) = &quota {
+            remember_quota(&state, &account, quota);
+        }
+        
         accounts_with_quota.push(AccountWithQuota {
             id: account.id.clone(),
             email: account.email.clone(),
@@ -130,10 +222,14 @@ async fn get_accounts(state: State<'_, AppState>) -> Result<AccountsResponse, Ap
             picture: account.picture.clone(),
             quota,
             is_active: account.is_active,
+            active_targets: account.active_targets.clone(),
             last_checked: account.last_checked,
         });
     }
     
+    // The tray shows the cached quotas; emitting accounts-updated here would reload the list again
+    tray::rebuild(&app_handle);
+    
     Ok(AccountsResponse {
         success: true,
         accounts: accounts_with_quota,
@@ -146,12 +242,23 @@ async fn add_account(
     email: String,
     refresh_token: String,
     name: Option<String>,
+    trigger: Option<Trigger>,
     state: State<'_, AppState>,
+    app_handle: tauri::AppHandle,
 ) -> Result<AccountResponse, ApiError> {
+    let started = std::time::Instant::now();
+    let entry = |outcome| AuditEntry::new(AuditEvent::Add, trigger.unwrap_or_default(), outcome).email(&email);
+    
     // Validate the token by trying to refresh it first (before acquiring lock)
     let token_data = match oauth::refresh_access_token(&refresh_token).await {
         Ok(token_data) => token_data,
-        Err(_) => {
+        Err(e) => {
+            audit::record(
+                entry(Outcome::Failure)
+                    .duration(started)
+                    .error_code("token_refresh_failed")
+                    .detail(e.to_string()),
+            );
             return Ok(AccountResponse {
                 success: false,
                 account: None,
@@ -160,8 +267,24 @@ async fn add_account(
         }
     };
     
-    let mut manager = state.account_manager.lock().unwrap();
-    let account = manager.add_account(email, refresh_token, name, Some(token_data))?;
+    let added = {
+        let mut manager = state.account_manager.lock().unwrap();
+        manager.add_account(email.clone(), refresh_token, name, Some(token_data))
+    };
+    let account = match added {
+        Ok(account) => account,
+        Err(e) => {
+            audit::record(
+                entry(Outcome::Failure)
+                    .duration(started)
+                    .error_code("add_failed")
+                    .detail(e.to_string()),
+            );
+            return Err(e.into());
+        }
+    };
+    audit::record(entry(Outcome::Success).duration(started));
+    tray::emit_accounts_updated(&app_handle);
     
     Ok(AccountResponse {
         success: true,
@@ -172,9 +295,32 @@ async fn add_account(
 
 /// Delete an account
 #[tauri::command]
-async fn delete_account(account_id: String, state: State<'_, AppState>) -> Result<bool, ApiError> {
-    let mut manager = state.account_manager.lock().unwrap();
-    manager.delete_account(&account_id)?;
+async fn delete_account(
+    account_id: String,
+    trigger: Option<Trigger>,
+    state: State<'_, AppState>,
+    app_handle: tauri::AppHandle,
+) -> Result<bool, ApiError> {
+    let trigger = trigger.unwrap_or_default();
+    let (email, deleted) = {
+        let mut manager = state.account_manager.lock().unwrap();
+        let email = manager.get_account(&account_id).map(|a| a.email);
+        (email, manager.delete_account(&account_id))
+    };
+    
+    if let Err(e) = deleted {
+        let mut entry = AuditEntry::new(AuditEvent::Delete, trigger, Outcome::Failure)
+            .error_code("delete_failed")
+            .detail(e.to_string());
+        entry.account_email = email;
+        audit::record(entry);
+        return Err(e.into());
+    }
+    
+    let mut entry = AuditEntry::new(AuditEvent::Delete, trigger, Outcome::Success);
+    entry.account_email = email;
+    audit::record(entry);
+    tray::emit_accounts_updated(&app_handle);
     Ok(true)
 }
 
@@ -182,91 +328,625 @@ async fn delete_account(account_id: String, state: State<'_, AppState>) -> Resul
 #[tauri::command]
 async fn switch_account(
     account_id: String,
-    state: State<'_, AppState>,
-    _app_handle: tauri::AppHandle,
+    target: Option<String>,
+    mode: Option<SwitchMode>,
+    dry_run: Option<bool>,
+    force: Option<bool>,
+    trigger: Option<Trigger>,
+    app_handle: tauri::AppHandle,
+) -> Result<SwitchResponse, ApiError> {
+    let options = SwitchOptions {
+        target,
+        mode,
+        dry_run: dry_run.unwrap_or(false),
+        force: force.unwrap_or(false),
+        trigger: trigger.unwrap_or_default(),
+    };
+    let dry_run = options.dry_run;
+    let response = perform_switch(&app_handle, &account_id, options).await?;
+    if response.success && !dry_run {
+        tray::emit_accounts_updated(&app_handle);
+    }
+    Ok(response)
+}
+
+/// Get an account's stored access token and its expiry if it is valid for at least 5 more minutes
+fn valid_access_token(account: &Account) -> Option<(String, TokenExpiry)> {
+    let access_token = account.access_token.clone()?;
+    let expires_at = account.expires_at?;
+    (!expires_at.is_within(chrono::Duration::minutes(5))).then_some((access_token, expires_at))
+}
+
+/// Make sure an account has a usable access token, refreshing it if needed
+async fn ensure_access_token(
+    state: &AppState,
+    account: &Account,
+    trigger: Trigger,
+) -> anyhow::Result<(String, TokenExpiry)> {
+    if let Some(valid) = valid_access_token(account) {
+        return Ok(valid);
+    }
+    
+    let started = std::time::Instant::now();
+    match oauth::refresh_access_token(&account.refresh_token).await {
+        Ok(token_data) => {
+            let mut manager = state.account_manager.lock().unwrap();
+            manager.update_account_token(&account.id, &token_data.access_token, token_data.expires_at)?;
+            if account.auth_revoked {
+                manager.set_auth_revoked(&account.id, false)?;
+            }
+            Ok((token_data.access_token, token_data.expires_at))
+        }
+        Err(e) => {
+            let revoked = oauth::is_revoked_error(&e);
+            if revoked && !account.auth_revoked {
+                state.notifier.notify(
+                    NotificationCategory::AuthRevoked,
+                    &account.id,
+                    "Sign-in expired",
+                    &format!("{} was signed out by Google - add the account again to keep using it", account.email),
+                );
+            }
+            audit::record(
+                AuditEntry::new(AuditEvent::TokenRefreshFailed, trigger, Outcome::Failure)
+                    .email(&account.email)
+                    .duration(started)
+                    .error_code(if revoked { "auth_revoked" } else { "token_refresh_failed" })
+                    .detail(e.to_string()),
+            );
+            if revoked {
+                let mut manager = state.account_manager.lock().unwrap();
+                manager.set_auth_revoked(&account.id, true)?;
+            }
+            Err(e)
+        }
+    }
+}
+
+/// Fetch quota for an account, refreshing its access token first if needed
+async fn fetch_fresh_quota(state: &AppState, account: &Account, trigger: Trigger) -> anyhow::Result<QuotaInfo> {
+    let (access_token, _) = ensure_access_token(state, account, trigger).await?;
+    let mut account = account.clone();
+    account.access_token = Some(access_token);
+    let quota = quota::fetch_quota(&account).await?;
+    remember_quota(state, &account, &quota);
+    Ok(quota)
+}
+
+/// Keep a freshly fetched quota for the tray and the reset scheduler, and warn when it runs low
+fn remember_quota(state: &AppState, account: &Account, quota: &QuotaInfo) {
+    state.reset_scheduler.track(account, quota);
+    let previous = state.quota_cache.lock().unwrap().insert(account.id.clone(), quota.clone());
+    
+    for model in notify::low_quota_crossings(previous.as_ref(), quota) {
+        let body = if model.percentage <= 0 {
+            format!("{} on {} is exhausted", model.display_name, account.email)
+        } else {
+            format!("{} on {} is down to {}%", model.display_name, account.email, model.percentage)
+        };
+        state.notifier.notify(
+            NotificationCategory::LowQuota,
+            &notify::low_quota_subject(&account.id, model),
+            "Quota running low",
+            &body,
+        );
+    }
+}
+
+/// Fetch quota for every account, skipping those whose tokens are revoked
+///
+/// The tray menu is rebuilt afterwards, since it shows the cached quotas.
+async fn collect_quotas(
+    app_handle: &tauri::AppHandle,
+    accounts: Vec<Account>,
+    trigger: Trigger,
+) -> Vec<(Account, Option<QuotaInfo>)> {
+    let state = app_handle.state::<AppState>();
+    let mut result = Vec::new();
+    
+    for account in accounts {
+        let quota = if account.auth_revoked {
+            None
+        } else {
+            fetch_fresh_quota(&state, &account, trigger).await.ok()
+        };
+        result.push((account, quota));
+    }
+    
+    tray::rebuild(app_handle);
+    result
+}
+
+/// How a switch is carried out
+#[derive(Debug, Clone, Default)]
+struct SwitchOptions {
+    /// Named IDE profile from settings; `None` switches the default IDE
+    target: Option<String>,
+    /// Whether the IDE is stopped and relaunched; `None` uses the mode from settings
+    mode: Option<SwitchMode>,
+    /// Only report what would happen
+    dry_run: bool,
+    /// Write even to an IDE version outside the supported range
+    force: bool,
+    /// What asked for the switch, for the audit log
+    trigger: Trigger,
+}
+
+/// Run a switch and record it in the audit log (dry runs are not recorded)
+async fn perform_switch(
+    app_handle: &tauri::AppHandle,
+    account_id: &str,
+    options: SwitchOptions,
+) -> Result<SwitchResponse, ApiError> {
+    let started = std::time::Instant::now();
+    let event = if options.trigger == Trigger::Auto {
+        AuditEvent::AutoSwitch
+    } else {
+        AuditEvent::Switch
+    };
+    let trigger = options.trigger;
+    let dry_run = options.dry_run;
+    let target = options.target.clone();
+    
+    if dry_run {
+        return run_switch(app_handle, account_id, options).await;
+    }
+    
+    // Overlapping switches are rejected rather than queued: a queued one would
+    // act on the accounts as they were before the running switch
+    let state = app_handle.state::<AppState>();
+    let result = match state.switch_lock.try_lock() {
+        Ok(_guard) => run_switch(app_handle, account_id, options).await,
+        Err(_) => Ok(SwitchResponse::failed("switch_in_progress", "Another switch is in progress")),
+    };
+    
+    let email = {
+        let manager = state.account_manager.lock().unwrap();
+        manager.get_account(account_id).map(|a| a.email)
+    };
+    let mut entry = switch_audit_entry(event, trigger, &result);
+    entry.account_email = email;
+    if let (Some(target), Outcome::Success) = (target, entry.outcome) {
+        entry = entry.detail(format!("target: {}", target));
+    }
+    audit::record(entry.duration(started));
+    
+    result
+}
+
+/// Switch in the background, for the tray and shortcuts, reporting the result as a notification
+///
+/// The switch applies to the tray target from settings.
+fn spawn_switch(app_handle: &tauri::AppHandle, account_id: String, trigger: Trigger) {
+    let app_handle = app_handle.clone();
+    tauri::async_runtime::spawn(async move {
+        let options = SwitchOptions {
+            target: tray::switch_target(),
+            trigger,
+            ..Default::default()
+        };
+        let (title, body) = match perform_switch(&app_handle, &account_id, options).await {
+            Ok(response) if response.success => {
+                tray::emit_accounts_updated(&app_handle);
+                ("Switched account", response.email.unwrap_or_default())
+            }
+            Ok(response) => ("Switch failed", response.error.unwrap_or_else(|| "Unknown error".to_string())),
+            Err(e) => ("Switch failed", e.error),
+        };
+        let state = app_handle.state::<AppState>();
+        state.notifier.notify(NotificationCategory::SwitchResult, &account_id, title, &body);
+    });
+}
+
+/// Build the audit entry for the result of a switch or revert
+fn switch_audit_entry(
+    event: AuditEvent,
+    trigger: Trigger,
+    result: &Result<SwitchResponse, ApiError>,
+) -> AuditEntry {
+    match result {
+        Ok(response) if response.success => AuditEntry::new(event, trigger, Outcome::Success),
+        Ok(response) => {
+            let mut entry = AuditEntry::new(event, trigger, Outcome::Failure);
+            entry.error_code = response.error_code.clone();
+            entry.detail = response.error.clone();
+            entry
+        }
+        Err(e) => AuditEntry::new(event, trigger, Outcome::Failure)
+            .error_code("internal")
+            .detail(&e.error),
+    }
+}
+
+/// Run the full switch sequence: refresh token, stop the IDE, inject, restart
+async fn run_switch(
+    app_handle: &tauri::AppHandle,
+    account_id: &str,
+    options: SwitchOptions,
 ) -> Result<SwitchResponse, ApiError> {
+    let state = app_handle.state::<AppState>();
+    let target = options.target.as_deref();
+    let mode = match options.mode {
+        Some(mode) => mode,
+        None => match settings::load_settings() {
+            Ok(settings) => settings.switch_mode,
+            Err(e) => {
+                eprintln!("Warning: Cannot read settings, using the default switch mode: {}", e);
+                SwitchMode::default()
+            }
+        },
+    };
+    
+    let ide = match install::resolve_target(target) {
+        Ok(ide) => ide,
+        Err(e) => return Ok(SwitchResponse::failed("target_not_found", e.to_string())),
+    };
+    
+    // The recipe for this IDE version decides which keys are written and cleared
+    let recipe = match recipe::select_for(&ide) {
+        Ok(recipe) => recipe,
+        Err(e) => return Ok(SwitchResponse::failed("no_recipe", e.to_string())),
+    };
+    
     // Get the account first, then release the lock
     let account = {
         let manager = state.account_manager.lock().unwrap();
-        match manager.get_account(&account_id) {
+        match manager.get_account(account_id) {
             Some(acc) => acc,
-            None => {
-                return Ok(SwitchResponse {
-                    success: false,
-                    email: None,
-                    error: Some("Account not found".to_string()),
-                });
-            }
+            None => return Ok(SwitchResponse::failed("account_not_found", "Account not found")),
         }
     };
     
-    // Refresh token if needed (outside the lock)
-    let access_token = if account.access_token.is_none() ||
-        account.expires_at.map(|exp| chrono::Utc::now().timestamp_millis() > exp - 300000).unwrap_or(true) {
-        match oauth::refresh_access_token(&account.refresh_token).await {
-            Ok(token_data) => {
-                // Re-acquire lock to update token
-                let mut manager = state.account_manager.lock().unwrap();
-                manager.update_account_token(&account_id, &token_data.access_token, token_data.expires_in)?;
-                token_data.access_token
+    let was_running = switch::is_target_running(&ide);
+    let refusal = (was_running && mode == SwitchMode::InjectOnly)
+        .then(|| "Antigravity is running - close it first or use another switch mode".to_string());
+    let launch = match mode {
+        SwitchMode::InjectOnly => false,
+        SwitchMode::RestartIfRunning => was_running,
+        SwitchMode::AlwaysLaunch => true,
+    };
+    
+    let version_check = switch::check_version(ide.version.as_deref(), Some(&recipe));
+    let warning = version_check.message.clone();
+    
+    if options.dry_run {
+        // Plan with the stored token - refreshing it would change the account.
+        // Without a valid one the expiry written depends on the refresh, so the
+        // stored one only fills the encoding and is reported as unknown below.
+        let valid = valid_access_token(&account);
+        let (access_token, expiry) = valid.clone().unwrap_or_else(|| {
+            (
+                account.access_token.clone().unwrap_or_default(),
+                account.expires_at.unwrap_or_else(|| TokenExpiry::from_expires_in(0)),
+            )
+        });
+        let mut plan = switch::plan_switch(
+            &ide,
+            &recipe,
+            was_running && refusal.is_none(),
+            launch,
+            switch::Credentials {
+                access_token: &access_token,
+                refresh_token: &account.refresh_token,
+                expiry,
+                email: &account.email,
+            },
+        )?;
+        plan.token_refresh_needed = valid.is_none();
+        if valid.is_none() {
+            if let Some(oauth) = &mut plan.oauth {
+                oauth.expiry = None;
             }
-            Err(_e) => {
-                return Ok(SwitchResponse {
-                    success: false,
-                    email: None,
-                    error: Some("Token refresh failed".to_string()),
-                });
+            plan.warnings.push(format!(
+                "The access token of {} will be refreshed before injecting, so its expiry is not known yet",
+                account.email
+            ));
+        }
+        plan.warnings.extend(refusal);
+        if version_check.status == switch::VersionStatus::Unsupported && !options.force {
+            plan.warnings.push(format!("{} (switch with force to proceed anyway)", warning.clone().unwrap_or_default()));
+        }
+        // A refresh is not attempted here, but these accounts are known to fail one
+        if valid.is_none() && account.auth_revoked {
+            plan.warnings.push(format!("Token refresh would fail - {} was signed out by Google", account.email));
+        } else if valid.is_none() && account.refresh_token.is_empty() {
+            plan.warnings.push(format!("Token refresh would fail - {} has no refresh token", account.email));
+        }
+        if let Some(snapshot) = snapshot::load_snapshot(&ide, account_id)? {
+            plan.writes.extend(snapshot.entries.iter().map(|entry| switch::PlannedWrite {
+                key: entry.key.clone(),
+                action: "restore".to_string(),
+                before_size: None,
+                after_size: Some(entry.size()),
+            }));
+        }
+        
+        return Ok(SwitchResponse {
+            success: true,
+            email: Some(account.email.clone()),
+            error: None,
+            error_code: None,
+            ide_restarted: None,
+            plan: Some(plan),
+            warning,
+        });
+    }
+    
+    if let Some(refusal) = refusal {
+        return Ok(SwitchResponse::failed("ide_running", refusal));
+    }
+    
+    // Refuse to write a schema the IDE may not understand unless told to
+    if version_check.status == switch::VersionStatus::Unsupported && !options.force {
+        return Ok(SwitchResponse::failed(
+            "unsupported_version",
+            format!("{} (switch with force to proceed anyway)", warning.unwrap_or_default()),
+        ));
+    }
+    
+    // Refresh token if needed (outside the lock)
+    let (access_token, expiry) = match ensure_access_token(&state, &account, options.trigger).await {
+        Ok(token) => token,
+        Err(e) => return Ok(SwitchResponse::failed("token_refresh_failed", format!("Token refresh failed: {}", e))),
+    };
+    
+    // Read before the IDE is stopped, so no error below leaves it closed
+    let outgoing = {
+        let manager = state.account_manager.lock().unwrap();
+        manager.get_active_account_in(target)?
+    };
+    
+    // Kill Antigravity processes and wait until they are gone, remembering how they were started
+    let previous = if was_running {
+        match switch::kill_antigravity(&ide).await {
+            Ok(previous) => previous,
+            Err(e) => {
+                return Ok(SwitchResponse::failed(
+                    "ide_running",
+                    format!("Could not stop Antigravity: {}", e),
+                ));
             }
         }
     } else {
-        account.access_token.unwrap_or_default()
+        None
     };
     
-    // Kill Antigravity processes
-    if let Err(e) = switch::kill_antigravity().await {
-        eprintln!("Warning: Failed to kill Antigravity: {}", e);
-    }
-    
-    // Small delay to ensure processes are terminated
-    tokio::time::sleep(tokio::time::Duration::from_millis(500)).await;
+    // Back up and checkpoint the WAL - refuses while the database is still open.
+    // The backup is the pre-switch state that undo restores.
+    let backup_path = match switch::prepare_database(&ide) {
+        Ok(backup_path) => backup_path,
+        Err(e) => {
+            // Bring back the IDE we stopped - nothing has been written yet
+            let ide_restarted = if was_running {
+                Some(relaunch(&ide, previous.as_ref()).await)
+            } else {
+                None
+            };
+            return Ok(SwitchResponse {
+                ide_restarted,
+                ..SwitchResponse::failed("database_busy", format!("Cannot switch safely: {}", e))
+            });
+        }
+    };
     
-    // Clean lock files
-    switch::clean_lock_files();
+    // Keep the outgoing account's chat threads and assistant state before they are cleared;
+    // without a snapshot they would be lost, so nothing is written
+    if let Some(outgoing) = &outgoing {
+        if let Err(e) = snapshot::save_snapshot(&ide, &recipe, &outgoing.id) {
+            let ide_restarted = if was_running {
+                Some(relaunch(&ide, previous.as_ref()).await)
+            } else {
+                None
+            };
+            return Ok(SwitchResponse {
+                ide_restarted,
+                ..SwitchResponse::failed(
+                    "snapshot_failed",
+                    format!("Cannot save the IDE state of {}: {}", outgoing.email, e),
+                )
+            });
+        }
+    }
     
     // Inject token into database - FIXED with better error handling
-    let expiry = chrono::Utc::now().timestamp() + 3600;
-    match switch::inject_token_into_db(&access_token, &account.refresh_token, expiry, &account.email).await {
+    let credentials = switch::Credentials {
+        access_token: &access_token,
+        refresh_token: &account.refresh_token,
+        expiry,
+        email: &account.email,
+    };
+    match switch::inject_token_into_db(&ide, &recipe, credentials).await {
         Ok(_) => {
             // Mark account as active in a scope to ensure lock is released
             {
                 let mut manager = state.account_manager.lock().unwrap();
-                manager.set_active_account(&account_id)?;
+                // The IDE already uses the new account, so carry on to relaunch it
+                if let Err(e) = manager.set_active_account(account_id, target) {
+                    eprintln!("Warning: Failed to mark {} as active: {}", account.email, e);
+                }
             } // Lock released here before await
             
-            // Restart Antigravity
-            if let Err(e) = switch::restart_antigravity().await {
-                eprintln!("Warning: Failed to restart Antigravity: {}", e);
+            // Bring back the incoming account's own chat threads and assistant state
+            if let Err(e) = snapshot::restore_snapshot(&ide, account_id) {
+                eprintln!("Warning: Failed to restore IDE state for {}: {}", account.email, e);
             }
             
+            *state.last_switch.lock().unwrap() = match (outgoing, backup_path) {
+                (Some(outgoing), Some(backup_path)) if outgoing.id != account_id => Some(LastSwitch {
+                    target: options.target.clone(),
+                    previous_account_id: outgoing.id,
+                    account_id: account_id.to_string(),
+                    backup_path,
+                }),
+                _ => None,
+            };
+            
+            // Restart Antigravity
+            let ide_restarted = if launch {
+                Some(relaunch(&ide, previous.as_ref()).await)
+            } else {
+                None
+            };
+            
             Ok(SwitchResponse {
                 success: true,
                 email: Some(account.email.clone()),
                 error: None,
+                error_code: None,
+                ide_restarted,
+                plan: None,
+                warning,
             })
         }
-        Err(_e) => {
-            // Try to restart Antigravity anyway - the token might still work on next launch
-            let _ = switch::restart_antigravity().await;
+        Err(e) => {
+            // Bring back an IDE we stopped - the old session is still usable
+            let ide_restarted = if was_running && launch {
+                Some(relaunch(&ide, previous.as_ref()).await)
+            } else {
+                None
+            };
             
             Ok(SwitchResponse {
-                success: false,
-                email: None,
-                error: Some("Database injection failed".to_string()),
+                ide_restarted,
+                ..SwitchResponse::failed("injection_failed", format!("Database injection failed: {}", e))
             })
         }
     }
 }
 
+/// Undo the last switch: restore the database backup taken before it and reactivate the previous account
+#[tauri::command]
+async fn revert_last_switch(
+    trigger: Option<Trigger>,
+    app_handle: tauri::AppHandle,
+) -> Result<SwitchResponse, ApiError> {
+    let started = std::time::Instant::now();
+    let state = app_handle.state::<AppState>();
+    let result = match state.switch_lock.try_lock() {
+        Ok(_guard) => run_revert(&app_handle).await,
+        Err(_) => Ok(SwitchResponse::failed("switch_in_progress", "Another switch is in progress")),
+    };
+    
+    let mut entry = switch_audit_entry(AuditEvent::Revert, trigger.unwrap_or_default(), &result);
+    entry.account_email = result.as_ref().ok().and_then(|r| r.email.clone());
+    audit::record(entry.duration(started));
+    
+    if matches!(&result, Ok(response) if response.success) {
+        tray::emit_accounts_updated(&app_handle);
+    }
+    result
+}
+
+/// Run the revert sequence: stop the IDE, restore the backup, restart
+async fn run_revert(app_handle: &tauri::AppHandle) -> Result<SwitchResponse, ApiError> {
+    let state = app_handle.state::<AppState>();
+    let last = match state.last_switch.lock().unwrap().clone() {
+        Some(last) => last,
+        None => return Ok(SwitchResponse::failed("nothing_to_undo", "There is no switch to undo")),
+    };
+    let target = last.target.as_deref();
+    
+    let ide = match install::resolve_target(target) {
+        Ok(ide) => ide,
+        Err(e) => return Ok(SwitchResponse::failed("target_not_found", e.to_string())),
+    };
+    
+    // Only undo if nothing has switched this target since
+    let (active, previous_account) = {
+        let manager = state.account_manager.lock().unwrap();
+        (manager.get_active_account_in(target)?, manager.get_account(&last.previous_account_id))
+    };
+    if active.map(|a| a.id) != Some(last.account_id.clone()) {
+        *state.last_switch.lock().unwrap() = None;
+        return Ok(SwitchResponse::failed(
+            "active_account_changed",
+            "The active account changed since the last switch",
+        ));
+    }
+    let previous_account = match previous_account {
+        Some(account) => account,
+        None => return Ok(SwitchResponse::failed("account_not_found", "The previous account no longer exists")),
+    };
+    
+    let was_running = switch::is_target_running(&ide);
+    let previous = if was_running {
+        match switch::kill_antigravity(&ide).await {
+            Ok(previous) => previous,
+            Err(e) => {
+                return Ok(SwitchResponse::failed(
+                    "ide_running",
+                    format!("Could not stop Antigravity: {}", e),
+                ));
+            }
+        }
+    } else {
+        None
+    };
+    
+    // Keep what the reverted account did since the switch; the restore would overwrite it
+    let saved = recipe::select_for(&ide).and_then(|recipe| snapshot::save_snapshot(&ide, &recipe, &last.account_id));
+    if let Err(e) = saved {
+        let ide_restarted = if was_running {
+            Some(relaunch(&ide, previous.as_ref()).await)
+        } else {
+            None
+        };
+        return Ok(SwitchResponse {
+            ide_restarted,
+            ..SwitchResponse::failed("snapshot_failed", format!("Cannot save the IDE state before reverting: {}", e))
+        });
+    }
+    
+    if let Err(e) = switch::restore_database(&ide, &last.backup_path) {
+        let ide_restarted = if was_running {
+            Some(relaunch(&ide, previous.as_ref()).await)
+        } else {
+            None
+        };
+        return Ok(SwitchResponse {
+            ide_restarted,
+            ..SwitchResponse::failed("restore_failed", format!("Cannot restore the database: {}", e))
+        });
+    }
+    
+    {
+        let mut manager = state.account_manager.lock().unwrap();
+        manager.set_active_account(&last.previous_account_id, target)?;
+    }
+    *state.last_switch.lock().unwrap() = None;
+    
+    let ide_restarted = if was_running {
+        Some(relaunch(&ide, previous.as_ref()).await)
+    } else {
+        None
+    };
+    
+    Ok(SwitchResponse {
+        success: true,
+        email: Some(previous_account.email),
+        error: None,
+        error_code: None,
+        ide_restarted,
+        plan: None,
+        warning: None,
+    })
+}
+
+/// Relaunch the IDE after a switch, returning whether it came back up
+async fn relaunch(ide: &install::Installation, previous: Option<&switch::LaunchContext>) -> bool {
+    match switch::restart_antigravity(ide, previous).await {
+        Ok(true) => true,
+        Ok(false) => {
+            eprintln!("Warning: Antigravity did not come back up after the switch");
+            false
+        }
+        Err(e) => {
+            eprintln!("Warning: Failed to restart Antigravity: {}", e);
+            false
+        }
+    }
+}
+
 /// Get the currently active account
 #[tauri::command]
 async fn get_active_account(state: State<'_, AppState>) -> Result<AccountResponse, ApiError> {
@@ -288,16 +968,23 @@ struct SimpleAccount {
 
 /// Export accounts to JSON (full format)
 #[tauri::command]
-async fn export_accounts(state: State<'_, AppState>) -> Result<String, ApiError> {
+async fn export_accounts(trigger: Option<Trigger>, state: State<'_, AppState>) -> Result<String, ApiError> {
     let manager = state.account_manager.lock().unwrap();
     let accounts = manager.load_accounts()?;
     let json = serde_json::to_string_pretty(&accounts)?;
+    audit::record(
+        AuditEntry::new(AuditEvent::Export, trigger.unwrap_or_default(), Outcome::Success)
+            .detail(format!("{} accounts, full format", accounts.len())),
+    );
     Ok(json)
 }
 
 /// Export accounts to JSON (simple format - email and refresh_token only)
 #[tauri::command]
-async fn export_accounts_simple(state: State<'_, AppState>) -> Result<String, ApiError> {
+async fn export_accounts_simple(
+    trigger: Option<Trigger>,
+    state: State<'_, AppState>,
+) -> Result<String, ApiError> {
     let manager = state.account_manager.lock().unwrap();
     let accounts = manager.load_accounts()?;
     
@@ -311,6 +998,10 @@ async fn export_accounts_simple(state: State<'_, AppState>) -> Result<String, Ap
         .collect();
     
     let json = serde_json::to_string_pretty(&simple_accounts)?;
+    audit::record(
+        AuditEntry::new(AuditEvent::Export, trigger.unwrap_or_default(), Outcome::Success)
+            .detail(format!("{} accounts, simple format", simple_accounts.len())),
+    );
     Ok(json)
 }
 
@@ -318,8 +1009,11 @@ async fn export_accounts_simple(state: State<'_, AppState>) -> Result<String, Ap
 #[tauri::command]
 async fn import_accounts(
     json_data: String,
+    trigger: Option<Trigger>,
     state: State<'_, AppState>,
+    app_handle: tauri::AppHandle,
 ) -> Result<ImportResponse, ApiError> {
+    let trigger = trigger.unwrap_or_default();
     // Try to parse as array first
     let imported: Vec<Account> = match serde_json::from_str(&json_data) {
         Ok(accounts) => accounts,
@@ -339,9 +1033,11 @@ async fn import_accounts(
                             access_token: None,
                             expires_at: None,
                             is_active: false,
+                            active_targets: Vec::new(),
                             added_at: 0, // Will be set during import
                             last_switched: None,
                             last_checked: None,
+                            auth_revoked: false,
                         })
                         .collect()
                 }
@@ -378,9 +1074,11 @@ async fn import_accounts(
                                                 access_token: None,
                                                 expires_at: None,
                                                 is_active: false,
+                                                active_targets: Vec::new(),
                                                 added_at: 0,
                                                 last_switched: None,
                                                 last_checked: None,
+                                                auth_revoked: false,
                                             })
                                             .collect()
                                     }
@@ -411,9 +1109,26 @@ async fn import_accounts(
         });
     }
     
-    let mut manager = state.account_manager.lock().unwrap();
-    
-    let (added, updated) = manager.import_accounts(imported)?;
+    let imported = {
+        let mut manager = state.account_manager.lock().unwrap();
+        manager.import_accounts(imported)
+    };
+    let (added, updated) = match imported {
+        Ok(counts) => counts,
+        Err(e) => {
+            audit::record(
+                AuditEntry::new(AuditEvent::Import, trigger, Outcome::Failure)
+                    .error_code("import_failed")
+                    .detail(e.to_string()),
+            );
+            return Err(e.into());
+        }
+    };
+    audit::record(
+        AuditEntry::new(AuditEvent::Import, trigger, Outcome::Success)
+            .detail(format!("{} added, {} updated", added, updated)),
+    );
+    tray::emit_accounts_updated(&app_handle);
     
     Ok(ImportResponse {
         success: true,
@@ -436,9 +1151,34 @@ fn open_url(url: String) -> Result<(), ApiError> {
     Ok(())
 }
 
+/// Record an account added or updated by OAuth sign-in in the audit log
+fn record_oauth_add(added: anyhow::Result<Account>, trigger: Trigger) -> anyhow::Result<Account> {
+    match added {
+        Ok(account) => {
+            audit::record(
+                AuditEntry::new(AuditEvent::Add, trigger, Outcome::Success)
+                    .email(&account.email)
+                    .detail("OAuth sign-in"),
+            );
+            Ok(account)
+        }
+        Err(e) => {
+            audit::record(
+                AuditEntry::new(AuditEvent::Add, trigger, Outcome::Failure)
+                    .error_code("add_failed")
+                    .detail(format!("OAuth sign-in: {}", e)),
+            );
+            Err(e)
+        }
+    }
+}
+
 /// Start OAuth flow - opens browser and waits for callback
 #[tauri::command]
-async fn start_oauth_flow(app_handle: tauri::AppHandle) -> Result<AccountResponse, ApiError> {
+async fn start_oauth_flow(
+    trigger: Option<Trigger>,
+    app_handle: tauri::AppHandle,
+) -> Result<AccountResponse, ApiError> {
     println!("Starting OAuth flow...");
     // Get auth URL
     let auth_url = oauth::get_auth_url()?;
@@ -505,11 +1245,14 @@ async fn start_oauth_flow(app_handle: tauri::AppHandle) -> Result<AccountRespons
     // Add or update account
     println!("Updating account manager...");
     let state = app_handle.state::<AppState>();
-    let mut manager = state.account_manager.lock().unwrap();
-    let account = manager.add_or_update_oauth_account(user_info, tokens)?;
+    let account = {
+        let mut manager = state.account_manager.lock().unwrap();
+        manager.add_or_update_oauth_account(user_info, tokens)
+    };
+    let account = record_oauth_add(account, trigger.unwrap_or_default())?;
     
-    // Emit event to notify frontend
-    app_handle.emit_all("accounts-updated", ())?;
+    // Emit event to notify frontend and tray
+    tray::emit_accounts_updated(&app_handle);
     
     println!("OAuth flow complete!");
     Ok(AccountResponse {
@@ -523,7 +1266,9 @@ async fn start_oauth_flow(app_handle: tauri::AppHandle) -> Result<AccountRespons
 #[tauri::command]
 async fn handle_oauth_callback(
     code: String,
+    trigger: Option<Trigger>,
     state: State<'_, AppState>,
+    app_handle: tauri::AppHandle,
 ) -> Result<AccountResponse, ApiError> {
     // Exchange code for tokens
     let tokens = match oauth::exchange_code_for_tokens(&code).await {
@@ -550,8 +1295,12 @@ async fn handle_oauth_callback(
     };
     
     // Add or update account
-    let mut manager = state.account_manager.lock().unwrap();
-    let account = manager.add_or_update_oauth_account(user_info, tokens)?;
+    let account = {
+        let mut manager = state.account_manager.lock().unwrap();
+        manager.add_or_update_oauth_account(user_info, tokens)
+    };
+    let account = record_oauth_add(account, trigger.unwrap_or_default())?;
+    tray::emit_accounts_updated(&app_handle);
     
     Ok(AccountResponse {
         success: true,
@@ -565,6 +1314,7 @@ async fn handle_oauth_callback(
 async fn refresh_quota(
     account_id: String,
     state: State<'_, AppState>,
+    app_handle: tauri::AppHandle,
 ) -> Result<Option<QuotaInfo>, ApiError> {
     // Get account first, then release the lock before await
     let account = {
@@ -574,7 +1324,11 @@ async fn refresh_quota(
     
     if let Some(acc) = account {
         match quota::fetch_quota(&acc).await {
-            Ok(quota) => Ok(Some(quota)),
+            Ok(quota) => {
+                remember_quota(&state, &acc, &quota);
+                tray::emit_accounts_updated(&app_handle);
+                Ok(Some(quota))
+            }
             Err(_) => Ok(None),
         }
     } else {
@@ -582,12 +1336,249 @@ async fn refresh_quota(
     }
 }
 
+/// Rank all accounts by how well they can serve a model
+#[tauri::command]
+async fn recommend_account(
+    model: String,
+    state: State<'_, AppState>,
+    app_handle: tauri::AppHandle,
+) -> Result<RecommendResponse, ApiError> {
+    let accounts = {
+        let manager = state.account_manager.lock().unwrap();
+        manager.load_accounts()?
+    };
+    
+    let candidates = collect_quotas(&app_handle, accounts, Trigger::Ui).await;
+    let recommendations = recommend::rank_accounts(&candidates, &model);
+    
+    Ok(RecommendResponse {
+        success: true,
+        model,
+        recommendations,
+    })
+}
+
+/// Get audit log entries, newest first
+#[tauri::command]
+fn get_history(query: Option<HistoryQuery>) -> Result<Vec<AuditEntry>, ApiError> {
+    Ok(audit::load_history(&query.unwrap_or_default())?)
+}
+
+/// Export audit log entries as JSONL, oldest first
+#[tauri::command]
+fn export_history(query: Option<HistoryQuery>) -> Result<String, ApiError> {
+    Ok(audit::export_history(&query.unwrap_or_default())?)
+}
+
+/// Get the effective model catalog (user entries first, then built-in)
+#[tauri::command]
+fn get_model_catalog() -> Result<ModelCatalog, ApiError> {
+    Ok(ModelCatalog::load())
+}
+
+/// Get the environment and IDE compatibility report
+#[tauri::command]
+async fn get_diagnostics() -> Result<Diagnostics, ApiError> {
+    Ok(diagnostics::collect().await)
+}
+
+/// Get the effective switch recipes (user recipes first, then built-in)
+#[tauri::command]
+fn get_switch_recipes() -> Result<SwitchRecipes, ApiError> {
+    Ok(SwitchRecipes::load())
+}
+
+/// Get all application settings
+#[tauri::command]
+fn get_settings() -> Result<Settings, ApiError> {
+    Ok(settings::load_settings()?)
+}
+
+/// Replace all application settings
+#[tauri::command]
+fn update_settings(settings: Settings, app_handle: tauri::AppHandle) -> Result<Settings, ApiError> {
+    let mut settings = settings;
+    settings.installation.assign_ids();
+    settings.validate()?;
+    apply_settings(&app_handle, settings)
+}
+
+/// Save settings whose changed section was validated, then tell the frontend and the notifier
+///
+/// Other modules read settings.json when they need it, so they see the change
+/// without being told. The settings are saved even if a changed shortcut
+/// cannot be registered; the error names it.
+fn apply_settings(app_handle: &tauri::AppHandle, settings: Settings) -> Result<Settings, ApiError> {
+    // Settings that cannot be read count as changed shortcuts
+    let previous = settings::load_settings().ok();
+    settings::save_settings(&settings)?;
+    
+    app_handle
+        .state::<AppState>()
+        .notifier
+        .set_settings(settings.notifications.clone());
+    let _ = app_handle.emit_all(settings::SETTINGS_CHANGED, &settings);
+    
+    // Shortcuts are held by the OS, so they are swapped here rather than re-read on use
+    if previous.map(|p| p.shortcuts) != Some(settings.shortcuts.clone()) {
+        shortcuts::register(app_handle)?;
+    }
+    Ok(settings)
+}
+
+/// Get the auto-switch policy
+#[tauri::command]
+fn get_auto_switch_policy() -> Result<AutoSwitchPolicy, ApiError> {
+    Ok(settings::load_settings()?.auto_switch)
+}
+
+/// Update the auto-switch policy
+#[tauri::command]
+fn update_auto_switch_policy(
+    policy: AutoSwitchPolicy,
+    app_handle: tauri::AppHandle,
+) -> Result<AutoSwitchPolicy, ApiError> {
+    policy.validate()?;
+    let mut current = settings::load_settings()?;
+    current.auto_switch = policy;
+    Ok(apply_settings(&app_handle, current)?.auto_switch)
+}
+
+/// Get the notification settings
+#[tauri::command]
+fn get_notification_settings() -> Result<NotificationSettings, ApiError> {
+    Ok(settings::load_settings()?.notifications)
+}
+
+/// Update the notification settings
+#[tauri::command]
+fn update_notification_settings(
+    notifications: NotificationSettings,
+    app_handle: tauri::AppHandle,
+) -> Result<NotificationSettings, ApiError> {
+    let mut current = settings::load_settings()?;
+    current.notifications = notifications;
+    Ok(apply_settings(&app_handle, current)?.notifications)
+}
+
+/// Get the global shortcut settings
+#[tauri::command]
+fn get_shortcut_settings() -> Result<ShortcutSettings, ApiError> {
+    Ok(settings::load_settings()?.shortcuts)
+}
+
+/// Update the global shortcut settings and register them again
+#[tauri::command]
+fn update_shortcut_settings(
+    shortcuts: ShortcutSettings,
+    app_handle: tauri::AppHandle,
+) -> Result<ShortcutSettings, ApiError> {
+    shortcuts.validate()?;
+    let mut current = settings::load_settings()?;
+    current.shortcuts = shortcuts;
+    Ok(apply_settings(&app_handle, current)?.shortcuts)
+}
+
+/// Get the low-quota thresholds
+#[tauri::command]
+fn get_quota_thresholds() -> Result<QuotaThresholds, ApiError> {
+    Ok(settings::load_settings()?.quota_thresholds)
+}
+
+/// Update the low-quota thresholds
+#[tauri::command]
+fn update_quota_thresholds(
+    thresholds: QuotaThresholds,
+    app_handle: tauri::AppHandle,
+) -> Result<QuotaThresholds, ApiError> {
+    thresholds.validate()?;
+    let mut current = settings::load_settings()?;
+    current.quota_thresholds = thresholds;
+    Ok(apply_settings(&app_handle, current)?.quota_thresholds)
+}
+
+/// List detected Antigravity installations
+#[tauri::command]
+fn get_installations() -> Result<InstallationsResponse, ApiError> {
+    Ok(InstallationsResponse {
+        success: true,
+        installations: install::detect_installations(),
+        active: install::selected_installation().ok().map(|i| i.id),
+    })
+}
+
+/// Get the installation settings
+#[tauri::command]
+fn get_installation_settings() -> Result<InstallationSettings, ApiError> {
+    Ok(settings::load_settings()?.installation)
+}
+
+/// Update the installation settings (selected installation and custom entries)
+#[tauri::command]
+fn update_installation_settings(
+    installation: InstallationSettings,
+    app_handle: tauri::AppHandle,
+) -> Result<InstallationSettings, ApiError> {
+    let mut installation = installation;
+    installation.assign_ids();
+    installation.validate()?;
+    let mut current = settings::load_settings()?;
+    current.installation = installation;
+    Ok(apply_settings(&app_handle, current)?.installation)
+}
+
+/// Get the default switch mode
+#[tauri::command]
+fn get_switch_mode() -> Result<SwitchMode, ApiError> {
+    Ok(settings::load_settings()?.switch_mode)
+}
+
+/// Update the default switch mode
+#[tauri::command]
+fn update_switch_mode(
+    mode: SwitchMode,
+    app_handle: tauri::AppHandle,
+) -> Result<SwitchMode, ApiError> {
+    let mut current = settings::load_settings()?;
+    current.switch_mode = mode;
+    Ok(apply_settings(&app_handle, current)?.switch_mode)
+}
+
+/// Get the named IDE targets
+#[tauri::command]
+fn get_targets() -> Result<Vec<IdeTarget>, ApiError> {
+    Ok(settings::load_settings()?.targets)
+}
+
+/// Replace the named IDE targets
+#[tauri::command]
+fn update_targets(
+    targets: Vec<IdeTarget>,
+    app_handle: tauri::AppHandle,
+) -> Result<Vec<IdeTarget>, ApiError> {
+    settings::validate_targets(&targets)?;
+    let mut current = settings::load_settings()?;
+    // The tray falls back to the default IDE when its target is removed
+    if let Some(tray_target) = &current.tray_target {
+        if !targets.iter().any(|t| &t.name == tray_target) {
+            current.tray_target = None;
+        }
+    }
+    current.targets = targets;
+    Ok(apply_settings(&app_handle, current)?.targets)
+}
+
 // ==================== MAIN ====================
 
 fn main() {
     // Load environment variables from .env file
     dotenv::dotenv().ok();
     
+    // Give custom installations from older versions their ids before anything reads them
+    if let Err(e) = settings::migrate_settings() {
+        eprintln!("Warning: Failed to migrate settings: {}", e);
+    }
+    
     // Initialize account manager
     let account_manager = match AccountManager::new() {
         Ok(am) => Mutex::new(am),
@@ -597,9 +1588,16 @@ fn main() {
         }
     };
 
-    let app_state = AppState { account_manager };
+    let app_state = AppState {
+        account_manager,
+        reset_scheduler: ResetScheduler::default(),
+        quota_cache: Mutex::new(HashMap::new()),
+        notifier: Notifier::default(),
+        last_switch: Mutex::new(None),
+        switch_lock: tokio::sync::Mutex::new(()),
+    };
 
-    // Create system tray menu
+    // Create system tray menu - replaced with the account list once the app is set up
     let tray_menu = SystemTrayMenu::new()
         .add_item(CustomMenuItem::new("show", "Show"))
         .add_native_item(tauri::SystemTrayMenuItem::Separator)
@@ -615,6 +1613,24 @@ fn main() {
             let window = app.get_window("main").unwrap();
             window.show().unwrap();
             window.set_focus().unwrap();
+            
+            // Notifications are sent as this app
+            app.state::<AppState>().notifier.init(&app.config().tauri.bundle.identifier);
+            
+            // List the accounts in the tray and keep the list current
+            tray::rebuild(&app.handle());
+            tray::listen(&app.handle());
+            
+            // Switch accounts from anywhere
+            if let Err(e) = shortcuts::register(&app.handle()) {
+                eprintln!("Warning: {}", e);
+            }
+            
+            // Watch the active account's quota in the background
+            autoswitch::spawn(app.handle());
+            
+            // Re-check quotas as soon as they reset
+            scheduler::spawn(app.handle());
             Ok(())
         })
         .on_system_tray_event(|app, event| match event {
@@ -623,17 +1639,7 @@ fn main() {
                 window.show().unwrap();
                 window.set_focus().unwrap();
             }
-            SystemTrayEvent::MenuItemClick { id, .. } => match id.as_str() {
-                "show" => {
-                    let window = app.get_window("main").unwrap();
-                    window.show().unwrap();
-                    window.set_focus().unwrap();
-                }
-                "quit" => {
-                    std::process::exit(0);
-                }
-                _ => {}
-            },
+            SystemTrayEvent::MenuItemClick { id, .. } => tray::handle_menu_click(app, &id),
             _ => {}
         })
         .invoke_handler(tauri::generate_handler![
@@ -650,6 +1656,30 @@ fn main() {
             start_oauth_flow,
             handle_oauth_callback,
             refresh_quota,
+            revert_last_switch,
+            recommend_account,
+            get_history,
+            export_history,
+            get_model_catalog,
+            get_switch_recipes,
+            get_diagnostics,
+            get_settings,
+            update_settings,
+            get_auto_switch_policy,
+            update_auto_switch_policy,
+            get_notification_settings,
+            update_notification_settings,
+            get_shortcut_settings,
+            update_shortcut_settings,
+            get_quota_thresholds,
+            update_quota_thresholds,
+            get_installations,
+            get_installation_settings,
+            update_installation_settings,
+            get_targets,
+            update_targets,
+            get_switch_mode,
+            update_switch_mode,
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
@@ -0,0 +1,136 @@
+// Desktop notifications - per-category toggles and rate limiting
+
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+use crate::quota::{Badge, ModelQuota, QuotaInfo};
+use crate::settings::{self, NotificationSettings};
+
+/// Kinds of notification, each with its own toggle in settings
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum NotificationCategory {
+    SwitchResult,
+    LowQuota,
+    QuotaReset,
+    AuthRevoked,
+}
+
+impl NotificationCategory {
+    fn is_enabled(self, settings: &NotificationSettings) -> bool {
+        settings.enabled
+            && match self {
+                NotificationCategory::SwitchResult => settings.switch_result,
+                NotificationCategory::LowQuota => settings.low_quota,
+                NotificationCategory::QuotaReset => settings.quota_reset,
+                NotificationCategory::AuthRevoked => settings.auth_revoked,
+            }
+    }
+
+    /// Background categories repeat with every refresh; switch results are one per switch
+    fn is_rate_limited(self) -> bool {
+        self != NotificationCategory::SwitchResult
+    }
+}
+
+/// Shows desktop notifications, dropping disabled and repeated ones
+#[derive(Default)]
+pub struct Notifier {
+    /// Bundle identifier the notifications are sent as, set once the app is up
+    identifier: OnceLock<String>,
+    /// Toggles and rate limit, kept here so a notification does not read settings.json
+    settings: Mutex<NotificationSettings>,
+    /// When each (category, subject) was last shown
+    last_sent: Mutex<HashMap<(NotificationCategory, String), Instant>>,
+}
+
+impl Notifier {
+    /// Set the bundle identifier and load the settings; notifications before this are dropped
+    pub fn init(&self, identifier: &str) {
+        let _ = self.identifier.set(identifier.to_string());
+        self.set_settings(
+            settings::load_settings()
+                .map(|s| s.notifications)
+                .unwrap_or_default(),
+        );
+    }
+
+    /// Replace the settings after they were saved
+    pub fn set_settings(&self, settings: NotificationSettings) {
+        *self.settings.lock().unwrap() = settings;
+    }
+
+    /// Show a notification about `subject` (an account or account/model pair)
+    ///
+    /// Returns whether it was shown.
+    pub fn notify(&self, category: NotificationCategory, subject: &str, title: &str, body: &str) -> bool {
+        let settings = self.settings.lock().unwrap().clone();
+        if !category.is_enabled(&settings) {
+            return false;
+        }
+
+        if category.is_rate_limited() {
+            let min_interval = Duration::from_secs(settings.min_interval_minutes as u64 * 60);
+            let mut last_sent = self.last_sent.lock().unwrap();
+            let key = (category, subject.to_string());
+            if let Some(sent) = last_sent.get(&key) {
+                if sent.elapsed() < min_interval {
+                    return false;
+                }
+            }
+            last_sent.insert(key, Instant::now());
+        }
+
+        let identifier = match self.identifier.get() {
+            Some(identifier) => identifier,
+            None => return false,
+        };
+        if let Err(e) = tauri::api::notification::Notification::new(identifier)
+            .title(title)
+            .body(body)
+            .show()
+        {
+            eprintln!("Warning: Failed to show notification: {}", e);
+            return false;
+        }
+        true
+    }
+}
+
+/// How serious a quota badge is, from 0 (none) to 3 (exhausted)
+fn severity(badge: Option<&Badge>) -> u8 {
+    match badge.map(|b| b.badge_type.as_str()) {
+        Some("exhausted") => 3,
+        Some("critical") => 2,
+        Some("warning") => 1,
+        _ => 0,
+    }
+}
+
+/// Rate-limit subject of a low-quota notification
+///
+/// The badge is part of it, so a model getting worse (warning, then critical,
+/// then exhausted) is announced at every step rather than only the first.
+pub fn low_quota_subject(account_id: &str, model: &ModelQuota) -> String {
+    format!("{}/{}/{}", account_id, model.name, severity(model.badge.as_ref()))
+}
+
+/// Models whose quota badge got more serious between two fetches
+///
+/// Nothing is reported without a previous fetch, so starting the app does not
+/// announce every account that is already low.
+pub fn low_quota_crossings<'a>(previous: Option<&QuotaInfo>, current: &'a QuotaInfo) -> Vec<&'a ModelQuota> {
+    let previous = match previous {
+        Some(previous) => previous,
+        None => return Vec::new(),
+    };
+
+    current
+        .models
+        .iter()
+        .filter(|model| {
+            let before = previous.find_model(&model.name).and_then(|m| m.badge.as_ref());
+            severity(model.badge.as_ref()) > severity(before)
+        })
+        .collect()
+}
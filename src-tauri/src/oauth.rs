@@ -1,9 +1,10 @@
 // OAuth handling - token refresh and user info
 
+use chrono::{DateTime, Duration, TimeZone, Utc};
 use serde::{Deserialize, Serialize};
 use std::env;
 
-const REDIRECT_URI: &str = "http://localhost:3847/auth/callback";
+use crate::settings::{self, OAuthSettings};
 
 // Compile-time environment variables (set during CI/build)
 const CLIENT_ID_COMPILE_TIME: Option<&str> = option_env!("GOOGLE_CLIENT_ID");
@@ -13,10 +14,45 @@ const CLIENT_SECRET_COMPILE_TIME: Option<&str> = option_env!("GOOGLE_CLIENT_SECR
 pub struct TokenData {
     pub access_token: String,
     pub refresh_token: String,
-    pub expires_in: i64,
+    pub expires_at: TokenExpiry,
     pub id_token: Option<String>,
 }
 
+/// When an access token stops being valid
+///
+/// Stored as Unix milliseconds in accounts.json and written into the IDE as a
+/// protobuf Timestamp with seconds and nanos.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct TokenExpiry(#[serde(with = "chrono::serde::ts_milliseconds")] DateTime<Utc>);
+
+impl TokenExpiry {
+    /// Expiry for a token issued now that is valid for `expires_in` seconds
+    pub fn from_expires_in(expires_in: i64) -> Self {
+        TokenExpiry(Utc::now() + Duration::seconds(expires_in))
+    }
+    
+    /// Build an expiry from a protobuf Timestamp
+    pub fn from_timestamp(seconds: i64, nanos: u32) -> Option<Self> {
+        Utc.timestamp_opt(seconds, nanos).single().map(TokenExpiry)
+    }
+    
+    /// Check whether the token expires within `margin` from now (or already has)
+    pub fn is_within(&self, margin: Duration) -> bool {
+        self.0 - Utc::now() < margin
+    }
+    
+    /// The expiry as protobuf Timestamp seconds and nanos
+    pub fn timestamp(&self) -> (i64, u32) {
+        (self.0.timestamp(), self.0.timestamp_subsec_nanos())
+    }
+}
+
+/// Read the token lifetime from a token endpoint response
+fn parse_expiry(token_response: &serde_json::Value) -> TokenExpiry {
+    TokenExpiry::from_expires_in(token_response["expires_in"].as_i64().unwrap_or(3600))
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct UserInfo {
     pub email: String,
@@ -45,7 +81,7 @@ fn get_client_id() -> anyhow::Result<String> {
         Please set up your Google OAuth credentials:\n\
         1. Go to https://console.cloud.google.com/apis/credentials\n\
         2. Create OAuth 2.0 credentials (Web application)\n\
-        3. Add http://localhost:3847/auth/callback as an authorized redirect URI\n\
+        3. Add http://localhost:3847/auth/callback (or the port set in settings) as an authorized redirect URI\n\
         4. Set GOOGLE_CLIENT_ID as an environment variable or in GitHub repository secrets"
     ))
 }
@@ -75,6 +111,16 @@ fn get_client_secret() -> anyhow::Result<String> {
     ))
 }
 
+/// Get the OAuth callback server settings
+fn oauth_settings() -> OAuthSettings {
+    settings::load_settings().map(|s| s.oauth).unwrap_or_default()
+}
+
+/// Redirect URI served by the local callback server
+fn redirect_uri(oauth: &OAuthSettings) -> String {
+    format!("http://localhost:{}/auth/callback", oauth.callback_port)
+}
+
 /// Get OAuth authorization URL
 pub fn get_auth_url() -> anyhow::Result<String> {
     let client_id = get_client_id()?;
@@ -89,7 +135,7 @@ pub fn get_auth_url() -> anyhow::Result<String> {
     let auth_url = format!(
         "https://accounts.google.com/o/oauth2/v2/auth?client_id={}&redirect_uri={}&response_type=code&scope={}&access_type=offline&prompt=consent",
         client_id,
-        REDIRECT_URI,
+        redirect_uri(&oauth_settings()),
         scopes.join("%20")
     );
     
@@ -103,11 +149,12 @@ pub async fn exchange_code_for_tokens(code: &str) -> anyhow::Result<TokenData> {
     
     let client = reqwest::Client::new();
     
+    let redirect_uri = redirect_uri(&oauth_settings());
     let params = [
         ("client_id", client_id.as_str()),
         ("client_secret", client_secret.as_str()),
         ("code", code),
-        ("redirect_uri", REDIRECT_URI),
+        ("redirect_uri", redirect_uri.as_str()),
         ("grant_type", "authorization_code"),
     ];
     
@@ -133,9 +180,7 @@ pub async fn exchange_code_for_tokens(code: &str) -> anyhow::Result<TokenData> {
             .as_str()
             .ok_or_else(|| anyhow::anyhow!("Missing refresh_token - make sure you included 'access_type=offline' and 'prompt=consent' in the auth URL"))?
             .to_string(),
-        expires_in: token_response["expires_in"]
-            .as_i64()
-            .unwrap_or(3600),
+        expires_at: parse_expiry(&token_response),
         id_token: token_response["id_token"].as_str().map(|s| s.to_string()),
     })
 }
@@ -173,13 +218,16 @@ pub async fn refresh_access_token(refresh_token: &str) -> anyhow::Result<TokenDa
             .ok_or_else(|| anyhow::anyhow!("Missing access_token in refresh response"))?
             .to_string(),
         refresh_token: refresh_token.to_string(), // Keep the original refresh token
-        expires_in: token_response["expires_in"]
-            .as_i64()
-            .unwrap_or(3600),
+        expires_at: parse_expiry(&token_response),
         id_token: token_response["id_token"].as_str().map(|s| s.to_string()),
     })
 }
 
+/// Check whether a refresh failure means the refresh token is no longer valid
+pub fn is_revoked_error(err: &anyhow::Error) -> bool {
+    err.to_string().contains("invalid_grant")
+}
+
 /// Fetch user info using access token
 pub async fn fetch_user_info(access_token: &str) -> anyhow::Result<UserInfo> {
     let client = reqwest::Client::new();
@@ -213,11 +261,12 @@ pub async fn start_oauth_server() -> anyhow::Result<String> {
     use tokio::io::{AsyncReadExt, AsyncWriteExt};
     use std::time::Duration;
     
-    let listener = TcpListener::bind("127.0.0.1:3847").await?;
-    println!("OAuth callback server listening on http://localhost:3847");
+    let oauth = oauth_settings();
+    let listener = TcpListener::bind(("127.0.0.1", oauth.callback_port)).await?;
+    println!("OAuth callback server listening on http://localhost:{}", oauth.callback_port);
     
-    // Set a timeout for the OAuth callback (5 minutes)
-    let timeout = tokio::time::timeout(Duration::from_secs(300), async {
+    // Set a timeout for the OAuth callback
+    let timeout = tokio::time::timeout(Duration::from_secs(oauth.timeout_minutes as u64 * 60), async {
         loop {
             let (mut socket, _) = listener.accept().await?;
             let mut buffer = [0u8; 4096];
@@ -261,7 +310,10 @@ pub async fn start_oauth_server() -> anyhow::Result<String> {
     match timeout.await {
         Ok(Ok(code)) => Ok(code),
         Ok(Err(e)) => Err(e),
-        Err(_) => Err(anyhow::anyhow!("OAuth timeout - no response received within 5 minutes")),
+        Err(_) => Err(anyhow::anyhow!(
+            "OAuth timeout - no response received within {} minutes",
+            oauth.timeout_minutes
+        )),
     }
 }
 
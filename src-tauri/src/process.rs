@@ -0,0 +1,243 @@
+// Process discovery and termination for the Antigravity IDE
+
+use std::path::Path;
+use std::process::Command;
+use std::time::Duration;
+
+/// Executable names of the IDE on Linux
+#[cfg(target_os = "linux")]
+const IDE_EXECUTABLES: &[&str] = &["antigravity", "Antigravity"];
+
+/// How often process liveness is polled while waiting for exit
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// A running IDE process
+#[cfg(target_os = "linux")]
+#[derive(Debug, Clone)]
+pub struct IdeProcess {
+    pub pid: u32,
+    pub cmdline: Vec<String>,
+    /// Working directory, which holds the folder the IDE was opened from
+    pub cwd: Option<std::path::PathBuf>,
+}
+
+#[cfg(target_os = "linux")]
+impl IdeProcess {
+    /// The `--user-data-dir` the process was started with, if any
+    pub fn user_data_dir(&self) -> Option<std::path::PathBuf> {
+        let mut args = self.cmdline.iter();
+        while let Some(arg) = args.next() {
+            if let Some(dir) = arg.strip_prefix("--user-data-dir=") {
+                return Some(dir.into());
+            }
+            if arg == "--user-data-dir" {
+                return args.next().map(|dir| dir.into());
+            }
+        }
+        None
+    }
+
+    /// Whether this is the main process rather than an Electron helper
+    ///
+    /// Renderer, GPU and utility processes are started with `--type=<kind>`.
+    pub fn is_main(&self) -> bool {
+        !self.cmdline.iter().skip(1).any(|arg| arg.starts_with("--type="))
+    }
+}
+
+/// Find running IDE processes by their executable, excluding our own process
+#[cfg(target_os = "linux")]
+pub fn find_ide_processes() -> Vec<IdeProcess> {
+    let own_pid = std::process::id();
+    let mut processes = Vec::new();
+
+    for pid in list_pids() {
+        if pid == own_pid {
+            continue;
+        }
+
+        // Only processes we own are readable, which is exactly the set we may signal
+        let exe = match std::fs::read_link(format!("/proc/{}/exe", pid)) {
+            Ok(exe) => exe,
+            Err(_) => continue,
+        };
+
+        // A replaced binary shows up as "/path/antigravity (deleted)"
+        let exe_name = exe
+            .file_name()
+            .map(|n| n.to_string_lossy().trim_end_matches(" (deleted)").to_string())
+            .unwrap_or_default();
+
+        if IDE_EXECUTABLES.contains(&exe_name.as_str()) && is_alive(pid) {
+            processes.push(IdeProcess {
+                pid,
+                cmdline: read_cmdline(pid),
+                cwd: std::fs::read_link(format!("/proc/{}/cwd", pid)).ok(),
+            });
+        }
+    }
+
+    processes
+}
+
+/// Read a process's NUL-separated command line
+#[cfg(target_os = "linux")]
+fn read_cmdline(pid: u32) -> Vec<String> {
+    std::fs::read(format!("/proc/{}/cmdline", pid))
+        .map(|raw| {
+            raw.split(|b| *b == 0)
+                .filter(|arg| !arg.is_empty())
+                .map(|arg| String::from_utf8_lossy(arg).to_string())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// List the numeric entries of /proc
+#[cfg(target_os = "linux")]
+fn list_pids() -> Vec<u32> {
+    std::fs::read_dir("/proc")
+        .map(|entries| {
+            entries
+                .flatten()
+                .filter_map(|entry| entry.file_name().to_str().and_then(|s| s.parse().ok()))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Check whether a process still exists and is not a zombie
+#[cfg(target_os = "linux")]
+pub fn is_alive(pid: u32) -> bool {
+    match std::fs::read_to_string(format!("/proc/{}/stat", pid)) {
+        // The state follows the parenthesised command name, which may contain spaces
+        Ok(stat) => stat
+            .rsplit_once(')')
+            .and_then(|(_, rest)| rest.trim_start().chars().next())
+            .map(|state| state != 'Z')
+            .unwrap_or(false),
+        Err(_) => false,
+    }
+}
+
+/// Send a signal (e.g. "TERM", "KILL") to each process
+#[cfg(target_os = "linux")]
+pub fn signal(pids: &[u32], signal: &str) {
+    if pids.is_empty() {
+        return;
+    }
+
+    let _ = Command::new("kill")
+        .arg(format!("-{}", signal))
+        .args(pids.iter().map(|pid| pid.to_string()))
+        .output();
+}
+
+/// Wait until all processes have exited, returning the ones still alive at the deadline
+#[cfg(target_os = "linux")]
+pub async fn wait_for_exit(pids: &[u32], timeout: Duration) -> Vec<u32> {
+    let deadline = tokio::time::Instant::now() + timeout;
+
+    loop {
+        let alive: Vec<u32> = pids.iter().copied().filter(|pid| is_alive(*pid)).collect();
+        if alive.is_empty() || tokio::time::Instant::now() >= deadline {
+            return alive;
+        }
+        tokio::time::sleep(POLL_INTERVAL).await;
+    }
+}
+
+/// Check whether any IDE process is running
+#[cfg(not(target_os = "linux"))]
+pub fn is_ide_running() -> bool {
+    #[cfg(target_os = "macos")]
+    {
+        Command::new("pgrep")
+            .args(["-x", "Antigravity"])
+            .output()
+            .map(|output| output.status.success())
+            .unwrap_or(false)
+    }
+
+    #[cfg(target_os = "windows")]
+    {
+        Command::new("tasklist")
+            .args(["/FI", "IMAGENAME eq Antigravity.exe", "/NH"])
+            .output()
+            .map(|output| String::from_utf8_lossy(&output.stdout).contains("Antigravity.exe"))
+            .unwrap_or(false)
+    }
+}
+
+/// Wait until no IDE process is running, returning false if the deadline passes first
+#[cfg(not(target_os = "linux"))]
+pub async fn wait_until_stopped(timeout: Duration) -> bool {
+    let deadline = tokio::time::Instant::now() + timeout;
+
+    loop {
+        if !is_ide_running() {
+            return true;
+        }
+        if tokio::time::Instant::now() >= deadline {
+            return false;
+        }
+        tokio::time::sleep(POLL_INTERVAL).await;
+    }
+}
+
+/// Check whether any process has one of the given files open
+pub fn is_file_in_use(paths: &[&Path]) -> bool {
+    #[cfg(target_os = "linux")]
+    {
+        let targets: Vec<std::path::PathBuf> = paths
+            .iter()
+            .filter_map(|p| std::fs::canonicalize(p).ok())
+            .collect();
+        if targets.is_empty() {
+            return false;
+        }
+        let own_pid = std::process::id();
+
+        list_pids().into_iter().filter(|pid| *pid != own_pid).any(|pid| {
+            std::fs::read_dir(format!("/proc/{}/fd", pid))
+                .map(|fds| {
+                    fds.flatten().any(|fd| {
+                        std::fs::read_link(fd.path())
+                            .map(|target| targets.contains(&target))
+                            .unwrap_or(false)
+                    })
+                })
+                .unwrap_or(false)
+        })
+    }
+
+    #[cfg(target_os = "macos")]
+    {
+        let existing: Vec<&Path> = paths.iter().copied().filter(|p| p.exists()).collect();
+        if existing.is_empty() {
+            return false;
+        }
+
+        // lsof exits successfully only when at least one of the files is open
+        Command::new("lsof")
+            .arg("-t")
+            .args(existing)
+            .output()
+            .map(|output| output.status.success())
+            .unwrap_or(false)
+    }
+
+    #[cfg(target_os = "windows")]
+    {
+        use std::os::windows::fs::OpenOptionsExt;
+
+        // Opening without sharing fails while another process has the file open
+        paths.iter().filter(|p| p.exists()).any(|p| {
+            std::fs::OpenOptions::new()
+                .read(true)
+                .share_mode(0)
+                .open(p)
+                .is_err()
+        })
+    }
+}
@@ -1,16 +1,42 @@
 // Quota fetching from Antigravity/Google APIs
 
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 
-use crate::account::Account;
+use crate::account::{self, Account};
+use crate::catalog::{self, ModelCatalog};
+use crate::settings::{self, ThresholdLevels};
+
+/// Maximum number of samples kept per account and model
+const MAX_SAMPLES_PER_MODEL: usize = 288;
+
+/// Samples closer together than this replace each other instead of accumulating
+const MIN_SAMPLE_INTERVAL_MS: i64 = 60_000;
+
+/// Serializes read-modify-write of quota_history.json, so concurrent fetches keep each other's samples
+static HISTORY_LOCK: Mutex<()> = Mutex::new(());
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ModelQuota {
     pub name: String,
     pub display_name: String,
+    pub family: Option<String>,
+    pub color: Option<String>,
     pub percentage: i32,
-    pub reset_time: Option<String>,
+    pub remaining_fraction: f64,
+    pub reset_time: Option<DateTime<Utc>>,
+    /// Seconds until the quota resets, as of the fetch
+    pub resets_in_seconds: Option<i64>,
     pub badge: Option<Badge>,
+    /// Estimated time (ms since epoch) the quota runs out at the current burn rate
+    pub projected_exhaustion: Option<i64>,
+    /// Whether the quota resets before the projected exhaustion
+    pub will_reset_first: Option<bool>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -26,6 +52,25 @@ pub struct QuotaInfo {
     pub error: Option<String>,
 }
 
+impl QuotaInfo {
+    /// Find a model by its API name or display name
+    pub fn find_model(&self, model: &str) -> Option<&ModelQuota> {
+        self.models
+            .iter()
+            .find(|m| m.name == model || m.display_name == model)
+    }
+}
+
+/// A single observation of a model's remaining quota
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct QuotaSample {
+    timestamp: i64,
+    remaining_fraction: f64,
+}
+
+/// Quota samples keyed by account id, then by model name
+type QuotaHistory = HashMap<String, HashMap<String, Vec<QuotaSample>>>;
+
 /// Fetch quota information for an account
 pub async fn fetch_quota(account: &Account) -> anyhow::Result<QuotaInfo> {
     let access_token = account
@@ -37,7 +82,22 @@ pub async fn fetch_quota(account: &Account) -> anyhow::Result<QuotaInfo> {
     let project_id = fetch_project_id(access_token).await?;
     
     // Then fetch available models/quota
-    let models = fetch_available_models(access_token, &project_id).await?;
+    let catalog = ModelCatalog::load();
+    let mut models = fetch_available_models(&catalog, access_token, &project_id).await?;
+    
+    // Flag models that are running low for this account
+    let thresholds = settings::load_settings()
+        .map(|s| s.quota_thresholds)
+        .unwrap_or_default();
+    for model in &mut models {
+        let levels = thresholds.resolve(&catalog, &account.id, &model.name, &model.display_name);
+        model.badge = quota_badge(model, &levels);
+    }
+    
+    // Record the observation and project exhaustion from the history
+    if let Err(e) = apply_forecast(&account.id, &mut models) {
+        eprintln!("Warning: Failed to update quota history: {}", e);
+    }
     
     Ok(QuotaInfo {
         models,
@@ -78,7 +138,11 @@ async fn fetch_project_id(access_token: &str) -> anyhow::Result<String> {
 }
 
 /// Fetch available models and their quota
-async fn fetch_available_models(access_token: &str, project_id: &str) -> anyhow::Result<Vec<ModelQuota>> {
+async fn fetch_available_models(
+    catalog: &ModelCatalog,
+    access_token: &str,
+    project_id: &str,
+) -> anyhow::Result<Vec<ModelQuota>> {
     let client = reqwest::Client::new();
     
     let response = client
@@ -110,25 +174,23 @@ async fn fetch_available_models(access_token: &str, project_id: &str) -> anyhow:
                     .unwrap_or(0.0);
                 
                 let percentage = (remaining_fraction * 100.0) as i32;
+                let reset_time = parse_reset_time(quota_info["resetTime"].as_str());
                 
-                let display_name = normalize_model_name(name);
-                
-                let badge = if percentage <= 5 {
-                    Some(Badge {
-                        text: "Low".to_string(),
-                        color: "#FF453A".to_string(),
-                        badge_type: "warning".to_string(),
-                    })
-                } else {
-                    None
-                };
+                let display_name = normalize_model_name(catalog, name);
+                let entry = catalog.lookup(name);
                 
                 quotas.push(ModelQuota {
                     name: name.clone(),
                     display_name,
+                    family: entry.and_then(|e| e.family.clone()),
+                    color: entry.and_then(|e| e.color.clone()),
                     percentage,
-                    reset_time: quota_info["resetTime"].as_str().map(|s| s.to_string()),
-                    badge,
+                    remaining_fraction,
+                    reset_time,
+                    resets_in_seconds: reset_time.map(|t| (t - Utc::now()).num_seconds().max(0)),
+                    badge: None,
+                    projected_exhaustion: None,
+                    will_reset_first: None,
                 });
             }
         }
@@ -136,64 +198,250 @@ async fn fetch_available_models(access_token: &str, project_id: &str) -> anyhow:
     
     // Sort by priority
     quotas.sort_by(|a, b| {
-        let priority_a = get_model_priority(&a.name);
-        let priority_b = get_model_priority(&b.name);
+        let priority_a = get_model_priority(catalog, &a.name);
+        let priority_b = get_model_priority(catalog, &b.name);
         priority_a.cmp(&priority_b)
     });
     
     Ok(quotas)
 }
 
-/// Normalize model name for display
-fn normalize_model_name(name: &str) -> String {
-    let name_lower = name.to_lowercase();
-    
-    if name_lower.contains("claude") && name_lower.contains("sonnet") && !name_lower.contains("thinking") {
-        "Claude Sonnet 4.5".to_string()
-    } else if name_lower.contains("thinking") {
-        if name_lower.contains("opus") {
-            "Claude Opus 4.5 (Thinking)".to_string()
-        } else {
-            "Claude Sonnet 4.5 (Thinking)".to_string()
+/// Build the badge for a model's remaining quota, if it is low enough to flag
+fn quota_badge(model: &ModelQuota, levels: &ThresholdLevels) -> Option<Badge> {
+    if model.remaining_fraction <= 0.0 {
+        let text = match model.reset_time {
+            Some(reset) => format!(
+                "Exhausted until {}",
+                reset.with_timezone(&chrono::Local).format("%b %-d %H:%M")
+            ),
+            None => "Exhausted".to_string(),
+        };
+        
+        return Some(Badge {
+            text,
+            color: "#FF453A".to_string(),
+            badge_type: "exhausted".to_string(),
+        });
+    }
+    
+    if model.percentage <= levels.critical {
+        Some(Badge {
+            text: "Critical".to_string(),
+            color: "#FF453A".to_string(),
+            badge_type: "critical".to_string(),
+        })
+    } else if model.percentage <= levels.warning {
+        Some(Badge {
+            text: "Low".to_string(),
+            color: "#FF9F0A".to_string(),
+            badge_type: "warning".to_string(),
+        })
+    } else {
+        None
+    }
+}
+
+/// Get the quota history file path
+fn get_history_path() -> anyhow::Result<PathBuf> {
+    Ok(account::get_data_dir()?.join("quota_history.json"))
+}
+
+/// Load quota history from storage
+fn load_history() -> anyhow::Result<QuotaHistory> {
+    let path = get_history_path()?;
+    if !path.exists() {
+        return Ok(HashMap::new());
+    }
+    
+    let content = fs::read_to_string(&path)?;
+    Ok(serde_json::from_str(&content).unwrap_or_default())
+}
+
+/// Save quota history to storage
+fn save_history(history: &QuotaHistory) -> anyhow::Result<()> {
+    let path = get_history_path()?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(&path, serde_json::to_string(history)?)?;
+    Ok(())
+}
+
+/// Record the current quota of each model and fill in its forecast
+fn apply_forecast(account_id: &str, models: &mut [ModelQuota]) -> anyhow::Result<()> {
+    let _guard = HISTORY_LOCK.lock().unwrap();
+    let mut history = load_history()?;
+    let account_history = history.entry(account_id.to_string()).or_default();
+    let now = Utc::now().timestamp_millis();
+    
+    for model in models.iter_mut() {
+        let samples = account_history.entry(model.name.clone()).or_default();
+        let sample = QuotaSample {
+            timestamp: now,
+            remaining_fraction: model.remaining_fraction,
+        };
+        
+        // Skip samples that come too soon, so frequent polling still spreads them out
+        let too_soon = samples
+            .last()
+            .is_some_and(|last| now - last.timestamp < MIN_SAMPLE_INTERVAL_MS);
+        if !too_soon {
+            samples.push(sample);
         }
-    } else if name_lower.contains("opus") {
-        "Claude Opus 4.5".to_string()
-    } else if name_lower.contains("gemini") && name_lower.contains("pro") {
-        if name_lower.contains("high") {
-            "Gemini 3 Pro (High)".to_string()
-        } else if name_lower.contains("low") {
-            "Gemini 3 Pro (Low)".to_string()
-        } else {
-            "Gemini 3 Pro".to_string()
+        
+        if samples.len() > MAX_SAMPLES_PER_MODEL {
+            let excess = samples.len() - MAX_SAMPLES_PER_MODEL;
+            samples.drain(..excess);
+        }
+        
+        model.projected_exhaustion = project_exhaustion(samples);
+        model.will_reset_first = reset_comes_first(model.reset_time, model.projected_exhaustion, samples);
+    }
+    
+    save_history(&history)
+}
+
+/// Whether the quota resets before it runs out, unknown until the current window has two samples
+fn reset_comes_first(
+    reset_time: Option<DateTime<Utc>>,
+    projected_exhaustion: Option<i64>,
+    samples: &[QuotaSample],
+) -> Option<bool> {
+    match (reset_time, projected_exhaustion) {
+        (Some(reset), Some(exhaustion)) => Some(reset.timestamp_millis() <= exhaustion),
+        // Not being consumed, so the reset always comes first
+        (Some(_), None) if current_window(samples).len() >= 2 => Some(true),
+        _ => None,
+    }
+}
+
+/// The samples since the last reset, which is where the remaining quota last went up
+fn current_window(samples: &[QuotaSample]) -> &[QuotaSample] {
+    let window_start = samples
+        .windows(2)
+        .rposition(|pair| pair[1].remaining_fraction > pair[0].remaining_fraction)
+        .map(|i| i + 1)
+        .unwrap_or(0);
+    &samples[window_start..]
+}
+
+/// Estimate when the quota runs out from the samples since the last reset
+fn project_exhaustion(samples: &[QuotaSample]) -> Option<i64> {
+    let window = current_window(samples);
+    
+    let first = window.first()?;
+    let last = window.last()?;
+    let elapsed = last.timestamp - first.timestamp;
+    let consumed = first.remaining_fraction - last.remaining_fraction;
+    
+    if elapsed <= 0 || consumed <= 0.0 {
+        return None;
+    }
+    
+    if last.remaining_fraction <= 0.0 {
+        return Some(last.timestamp);
+    }
+    
+    let burn_rate = consumed / elapsed as f64; // fraction per ms
+    Some(last.timestamp + (last.remaining_fraction / burn_rate) as i64)
+}
+
+/// Parse an RFC 3339 reset time returned by the API
+fn parse_reset_time(reset_time: Option<&str>) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(reset_time?)
+        .ok()
+        .map(|t| t.with_timezone(&Utc))
+}
+
+/// Normalize model name for display
+fn normalize_model_name(catalog: &ModelCatalog, name: &str) -> String {
+    match catalog.lookup(name).and_then(|entry| entry.display_name.clone()) {
+        Some(display_name) => display_name,
+        None => {
+            // Clean up the name
+            let clean = name.split('/').next_back().unwrap_or(name);
+            clean.replace("-", " ").replace("_", " ")
         }
-    } else if name_lower.contains("gemini") && name_lower.contains("flash") {
-        "Gemini 3 Flash".to_string()
-    } else if name_lower.contains("gpt") && name_lower.contains("oss") {
-        "GPT-OSS 120B".to_string()
-    } else {
-        // Clean up the name
-        let clean = name.split('/').next_back().unwrap_or(name);
-        clean.replace("-", " ").replace("_", " ")
     }
 }
 
 /// Get model priority for sorting
-fn get_model_priority(name: &str) -> i32 {
-    let name_lower = name.to_lowercase();
-    
-    if name_lower.contains("claude") && name_lower.contains("sonnet") && !name_lower.contains("thinking") {
-        1
-    } else if name_lower.contains("thinking") {
-        2
-    } else if name_lower.contains("opus") {
-        3
-    } else if name_lower.contains("gemini") && name_lower.contains("pro") {
-        4
-    } else if name_lower.contains("gemini") && name_lower.contains("flash") {
-        5
-    } else if name_lower.contains("gpt") {
-        6
-    } else {
-        100
+fn get_model_priority(catalog: &ModelCatalog, name: &str) -> i32 {
+    catalog
+        .lookup(name)
+        .map(|entry| entry.priority)
+        .unwrap_or(catalog::FALLBACK_PRIORITY)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample(timestamp: i64, remaining_fraction: f64) -> QuotaSample {
+        QuotaSample {
+            timestamp,
+            remaining_fraction,
+        }
+    }
+
+    #[test]
+    fn projects_exhaustion_from_the_burn_rate() {
+        // 10% used per hour with 80% left runs out 8 hours after the last sample
+        let hour = 3_600_000;
+        let samples = [sample(0, 1.0), sample(hour, 0.9), sample(2 * hour, 0.8)];
+        assert_eq!(project_exhaustion(&samples), Some(10 * hour));
+    }
+
+    #[test]
+    fn projection_starts_at_the_last_reset() {
+        let hour = 3_600_000;
+        // Fast use before the reset at 2h must not count
+        let samples = [sample(0, 0.9), sample(hour, 0.1), sample(2 * hour, 1.0), sample(4 * hour, 0.9)];
+        assert_eq!(project_exhaustion(&samples), Some(4 * hour + 18 * hour));
+    }
+
+    #[test]
+    fn no_projection_without_consumption() {
+        assert_eq!(project_exhaustion(&[]), None);
+        assert_eq!(project_exhaustion(&[sample(0, 0.5)]), None);
+        assert_eq!(project_exhaustion(&[sample(0, 0.5), sample(60_000, 0.5)]), None);
+        assert_eq!(project_exhaustion(&[sample(0, 0.5), sample(60_000, 0.0)]), Some(60_000));
+    }
+
+    #[test]
+    fn reset_order_unknown_until_the_window_has_two_samples() {
+        let now = Utc::now();
+        let reset = Some(now);
+        assert_eq!(reset_comes_first(reset, None, &[]), None);
+        assert_eq!(reset_comes_first(reset, None, &[sample(0, 0.5)]), None);
+        // Just after a reset only the new sample is in the window
+        assert_eq!(reset_comes_first(reset, None, &[sample(0, 0.1), sample(60_000, 1.0)]), None);
+        assert_eq!(reset_comes_first(reset, None, &[sample(0, 0.5), sample(60_000, 0.5)]), Some(true));
+        assert_eq!(reset_comes_first(None, None, &[sample(0, 0.5), sample(60_000, 0.5)]), None);
+
+        let reset_ms = now.timestamp_millis();
+        assert_eq!(reset_comes_first(reset, Some(reset_ms + 1), &[]), Some(true));
+        assert_eq!(reset_comes_first(reset, Some(reset_ms - 1), &[]), Some(false));
+    }
+
+    #[test]
+    fn unmatched_models_fall_back_like_before_the_catalog() {
+        let catalog = ModelCatalog::builtin();
+        let cases = [
+            // Matched by a single keyword, without the vendor name
+            ("some-thinking-model", "Claude Sonnet 4.5 (Thinking)", 2),
+            ("opus-thinking", "Claude Opus 4.5 (Thinking)", 2),
+            ("opus-next", "Claude Opus 4.5", 3),
+            ("gemini-2.5-pro", "Gemini 3 Pro", 4),
+            ("gemini-2.5-flash", "Gemini 3 Flash", 5),
+            // GPT models without a display name keep the cleaned-up id
+            ("gpt-4o", "gpt 4o", 6),
+            ("models/new-model_v2", "new model v2", catalog::FALLBACK_PRIORITY),
+        ];
+
+        for (id, display_name, priority) in cases {
+            assert_eq!(normalize_model_name(&catalog, id), display_name, "{}", id);
+            assert_eq!(get_model_priority(&catalog, id), priority, "{}", id);
+        }
     }
 }
@@ -0,0 +1,188 @@
+// Switch recipes - which database keys a switch writes, clears and keeps
+
+use std::cmp::Ordering;
+use std::fs;
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+
+use crate::account;
+use crate::db;
+use crate::install::Installation;
+
+/// Recipes shipped with the app
+const DEFAULT_RECIPES: &str = include_str!("../switch_recipe.json");
+
+/// Versioned list of switch recipes
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SwitchRecipes {
+    pub version: u32,
+    pub recipes: Vec<SwitchRecipe>,
+}
+
+/// The keys a switch touches for a range of IDE versions
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SwitchRecipe {
+    pub name: String,
+    /// Lowest IDE version this recipe applies to (inclusive)
+    #[serde(default)]
+    pub min_version: Option<String>,
+    /// First IDE version this recipe no longer applies to (exclusive)
+    #[serde(default)]
+    pub max_version: Option<String>,
+    pub writes: Vec<KeyWrite>,
+    /// Keys deleted on every switch, together with their dotted sub-keys
+    #[serde(default)]
+    pub clear: Vec<String>,
+    /// Keys (and their dotted sub-keys) that are never deleted, even if cleared above
+    #[serde(default)]
+    pub keep: Vec<String>,
+}
+
+/// A key written during a switch
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KeyWrite {
+    pub key: String,
+    pub encoding: ValueEncoding,
+}
+
+/// How the value of a written key is built
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ValueEncoding {
+    /// Base64 of the protobuf OAuth token wrapper
+    OauthToken,
+    /// JSON with the email, access token and name
+    AuthStatus,
+}
+
+impl SwitchRecipes {
+    /// Load the built-in recipes with the user's taking precedence
+    pub fn load() -> Self {
+        let mut recipes = Self::builtin();
+
+        match load_user_recipes() {
+            Ok(Some(user)) => {
+                let mut merged = user.recipes;
+                merged.extend(recipes.recipes);
+                recipes.recipes = merged;
+                recipes.version = recipes.version.max(user.version);
+            }
+            Ok(None) => {}
+            Err(e) => eprintln!("Warning: Ignoring invalid switch recipe file: {}", e),
+        }
+
+        recipes
+    }
+
+    /// The recipes shipped with the app
+    pub fn builtin() -> Self {
+        serde_json::from_str(DEFAULT_RECIPES).expect("built-in switch recipes are valid JSON")
+    }
+
+    /// Find the first recipe that applies to an IDE version
+    pub fn select(&self, ide_version: Option<&str>) -> Option<&SwitchRecipe> {
+        self.recipes.iter().find(|recipe| recipe.matches(ide_version))
+    }
+}
+
+impl SwitchRecipe {
+    /// Check whether this recipe applies to an IDE version
+    ///
+    /// A recipe without bounds applies to every version, including an unknown
+    /// one; a bounded recipe only applies to a known version inside its range.
+    pub fn matches(&self, ide_version: Option<&str>) -> bool {
+        if self.min_version.is_none() && self.max_version.is_none() {
+            return true;
+        }
+
+        let version = match ide_version {
+            Some(version) => version,
+            None => return false,
+        };
+        let above_min = self
+            .min_version
+            .as_deref()
+            .map(|min| compare_versions(version, min) != Ordering::Less)
+            .unwrap_or(true);
+        let below_max = self
+            .max_version
+            .as_deref()
+            .map(|max| compare_versions(version, max) == Ordering::Less)
+            .unwrap_or(true);
+        above_min && below_max
+    }
+
+    /// SQL condition selecting the keys this recipe deletes
+    pub fn clear_condition(&self) -> String {
+        let cleared = key_prefix_condition(&self.clear);
+        if self.keep.is_empty() {
+            cleared
+        } else {
+            format!("({}) AND NOT ({})", cleared, key_prefix_condition(&self.keep))
+        }
+    }
+}
+
+/// SQL condition matching any of the keys or their dotted sub-keys
+fn key_prefix_condition(keys: &[String]) -> String {
+    if keys.is_empty() {
+        return "0".to_string();
+    }
+
+    keys.iter()
+        .map(|key| format!("key = {} OR key LIKE {}", db::quote(key), db::quote(&format!("{}.%", key))))
+        .collect::<Vec<_>>()
+        .join(" OR ")
+}
+
+/// Compare dotted version strings numerically, ignoring any pre-release suffix
+pub fn compare_versions(a: &str, b: &str) -> Ordering {
+    let parse = |version: &str| -> Vec<u64> {
+        version
+            .split(['-', '+'])
+            .next()
+            .unwrap_or_default()
+            .split('.')
+            .map(|part| part.trim().parse().unwrap_or(0))
+            .collect()
+    };
+
+    let (a, b) = (parse(a), parse(b));
+    for i in 0..a.len().max(b.len()) {
+        let ordering = a.get(i).unwrap_or(&0).cmp(b.get(i).unwrap_or(&0));
+        if ordering != Ordering::Equal {
+            return ordering;
+        }
+    }
+    Ordering::Equal
+}
+
+/// Select the recipe for an installation's detected IDE version
+pub fn select_for(target: &Installation) -> anyhow::Result<SwitchRecipe> {
+    SwitchRecipes::load()
+        .select(target.version.as_deref())
+        .cloned()
+        .ok_or_else(|| {
+            anyhow::anyhow!(
+                "No switch recipe supports Antigravity {}",
+                target.version.as_deref().unwrap_or("(unknown version)")
+            )
+        })
+}
+
+/// Get the user recipe file path
+pub fn get_user_recipe_path() -> anyhow::Result<PathBuf> {
+    Ok(account::get_data_dir()?.join("switch_recipe.json"))
+}
+
+/// Load the user's recipe overrides, if any
+fn load_user_recipes() -> anyhow::Result<Option<SwitchRecipes>> {
+    let path = get_user_recipe_path()?;
+    if !path.exists() {
+        return Ok(None);
+    }
+
+    let content = fs::read_to_string(&path)?;
+    Ok(Some(serde_json::from_str(&content)?))
+}
@@ -0,0 +1,123 @@
+// Account recommendation - rank accounts by how well they can serve a model
+
+use serde::{Deserialize, Serialize};
+
+use crate::account::Account;
+use crate::quota::QuotaInfo;
+
+/// Horizon used to weigh how soon a quota resets
+const RESET_HORIZON_SECS: i64 = 24 * 60 * 60;
+
+/// Horizon used to weigh how long ago an account was last used
+const RECENCY_HORIZON_MS: i64 = 24 * 60 * 60 * 1000;
+
+/// A ranked account with the reasons behind its position
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Recommendation {
+    pub account_id: String,
+    pub email: String,
+    pub rank: usize,
+    pub score: f64,
+    pub remaining_percentage: Option<i32>,
+    pub resets_in_seconds: Option<i64>,
+    pub is_active: bool,
+    pub auth_revoked: bool,
+    pub reasons: Vec<String>,
+}
+
+/// Rank accounts for a model, best first
+///
+/// The score is dominated by the remaining fraction for the model. Accounts
+/// whose quota resets soon get a bonus proportional to what they have used,
+/// accounts that have not been used recently get a small bonus to spread the
+/// load, and accounts with revoked tokens or no quota data sink to the bottom.
+pub fn rank_accounts(candidates: &[(Account, Option<QuotaInfo>)], model: &str) -> Vec<Recommendation> {
+    let now_ms = chrono::Utc::now().timestamp_millis();
+
+    let mut ranked: Vec<Recommendation> = candidates
+        .iter()
+        .map(|(account, quota)| {
+            let mut reasons = Vec::new();
+            let mut score = 0.0;
+
+            let model_quota = quota.as_ref().and_then(|q| q.find_model(model));
+            let remaining_percentage = model_quota.map(|m| m.percentage);
+            let resets_in_seconds = model_quota
+                .and_then(|m| m.reset_time)
+                .map(|reset| (reset.timestamp_millis() - now_ms).max(0) / 1000);
+
+            if account.auth_revoked {
+                reasons.push("Refresh token revoked - sign in again".to_string());
+                score -= 1000.0;
+            }
+
+            match model_quota {
+                Some(m) => {
+                    let remaining = m.remaining_fraction.clamp(0.0, 1.0);
+                    score += remaining * 100.0;
+                    reasons.push(format!("{}% remaining for {}", m.percentage, m.display_name));
+
+                    if let Some(resets_in) = resets_in_seconds {
+                        let closeness = 1.0 - resets_in.min(RESET_HORIZON_SECS) as f64 / RESET_HORIZON_SECS as f64;
+                        score += (1.0 - remaining) * closeness * 20.0;
+                        reasons.push(format!("Resets in {}", format_duration(resets_in)));
+                    }
+                }
+                None if quota.is_some() => {
+                    reasons.push(format!("{} is not available on this account", model));
+                    score -= 500.0;
+                }
+                None => {
+                    reasons.push("Quota unavailable".to_string());
+                    score -= 500.0;
+                }
+            }
+
+            match account.last_switched {
+                Some(last_switched) => {
+                    let idle = (now_ms - last_switched).clamp(0, RECENCY_HORIZON_MS);
+                    score += idle as f64 / RECENCY_HORIZON_MS as f64 * 5.0;
+                    reasons.push(format!("Last used {} ago", format_duration(idle / 1000)));
+                }
+                None => {
+                    score += 5.0;
+                    reasons.push("Never used".to_string());
+                }
+            }
+
+            Recommendation {
+                account_id: account.id.clone(),
+                email: account.email.clone(),
+                rank: 0,
+                score,
+                remaining_percentage,
+                resets_in_seconds,
+                is_active: account.is_active,
+                auth_revoked: account.auth_revoked,
+                reasons,
+            }
+        })
+        .collect();
+
+    ranked.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap_or(std::cmp::Ordering::Equal));
+    for (i, recommendation) in ranked.iter_mut().enumerate() {
+        recommendation.rank = i + 1;
+    }
+
+    ranked
+}
+
+/// Format a duration in seconds as a short human-readable string
+fn format_duration(secs: i64) -> String {
+    let days = secs / 86400;
+    let hours = (secs % 86400) / 3600;
+    let minutes = (secs % 3600) / 60;
+
+    if days > 0 {
+        format!("{}d {}h", days, hours)
+    } else if hours > 0 {
+        format!("{}h {}m", hours, minutes)
+    } else {
+        format!("{}m", minutes)
+    }
+}
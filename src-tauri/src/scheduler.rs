@@ -0,0 +1,149 @@
+// Quota reset scheduler - re-check quotas right after they reset
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use chrono::{DateTime, Utc};
+use tauri::Manager;
+
+use crate::account::Account;
+use crate::audit::Trigger;
+use crate::notify::NotificationCategory;
+use crate::quota::QuotaInfo;
+use crate::AppState;
+
+/// How often pending resets are checked
+const POLL_INTERVAL_SECS: u64 = 30;
+
+/// Delay after the reset time before re-fetching, so the API has caught up
+const RESET_GRACE_SECS: i64 = 30;
+
+/// A model whose quota is waiting for its reset time
+#[derive(Debug, Clone)]
+struct PendingReset {
+    reset_time: DateTime<Utc>,
+    display_name: String,
+    percentage: i32,
+}
+
+/// Tracks upcoming quota resets per account and model
+#[derive(Default)]
+pub struct ResetScheduler {
+    /// Keyed by (account id, model name)
+    pending: Mutex<HashMap<(String, String), PendingReset>>,
+}
+
+impl ResetScheduler {
+    /// Remember the reset times of every model that is not at full quota
+    ///
+    /// Reset times already in the past are dropped: re-fetching for them would
+    /// only get the same stale time back and fire again on every poll.
+    pub fn track(&self, account: &Account, quota: &QuotaInfo) {
+        let now = Utc::now();
+        let mut pending = self.pending.lock().unwrap();
+
+        for model in &quota.models {
+            let key = (account.id.clone(), model.name.clone());
+            match model.reset_time {
+                Some(reset_time) if model.percentage < 100 && reset_time > now => {
+                    pending.insert(key, PendingReset {
+                        reset_time,
+                        display_name: model.display_name.clone(),
+                        percentage: model.percentage,
+                    });
+                }
+                _ => {
+                    pending.remove(&key);
+                }
+            }
+        }
+    }
+
+    /// Remove and return the resets that are due, grouped by account id
+    fn take_due(&self) -> HashMap<String, Vec<(String, PendingReset)>> {
+        let now = Utc::now();
+        let mut pending = self.pending.lock().unwrap();
+        let mut due: HashMap<String, Vec<(String, PendingReset)>> = HashMap::new();
+
+        pending.retain(|(account_id, model), reset| {
+            if (now - reset.reset_time).num_seconds() >= RESET_GRACE_SECS {
+                due.entry(account_id.clone())
+                    .or_default()
+                    .push((model.clone(), reset.clone()));
+                false
+            } else {
+                true
+            }
+        });
+
+        due
+    }
+
+    /// Put back resets whose re-check failed, so the next poll retries them
+    ///
+    /// A reset tracked again in the meantime is newer and kept.
+    fn requeue(&self, account_id: &str, models: Vec<(String, PendingReset)>) {
+        let mut pending = self.pending.lock().unwrap();
+        for (model, reset) in models {
+            pending.entry((account_id.to_string(), model)).or_insert(reset);
+        }
+    }
+}
+
+/// Start the background task that re-fetches quotas after their reset time
+pub fn spawn(app_handle: tauri::AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        loop {
+            tokio::time::sleep(tokio::time::Duration::from_secs(POLL_INTERVAL_SECS)).await;
+            check_resets(&app_handle).await;
+        }
+    });
+}
+
+/// Re-fetch quotas for accounts with due resets and report restored models
+async fn check_resets(app_handle: &tauri::AppHandle) {
+    let state = app_handle.state::<AppState>();
+    let due = state.reset_scheduler.take_due();
+
+    for (account_id, models) in due {
+        let account = {
+            let manager = state.account_manager.lock().unwrap();
+            manager.get_account(&account_id)
+        };
+        let account = match account {
+            Some(account) => account,
+            None => continue,
+        };
+
+        // fetch_fresh_quota re-tracks the next reset for each model
+        let quota = match crate::fetch_fresh_quota(&state, &account, Trigger::Auto).await {
+            Ok(quota) => quota,
+            Err(e) => {
+                eprintln!("Warning: Failed to re-check quota for {}: {}", account.email, e);
+                // A revoked sign-in fails every time until the account is added again
+                if !crate::oauth::is_revoked_error(&e) {
+                    state.reset_scheduler.requeue(&account_id, models);
+                }
+                continue;
+            }
+        };
+
+        for (model, previous) in models {
+            if let Some(current) = quota.find_model(&model) {
+                if current.percentage > previous.percentage {
+                    state.notifier.notify(
+                        NotificationCategory::QuotaReset,
+                        &format!("{}/{}", account.id, model),
+                        "Quota restored",
+                        &format!(
+                            "{} on {} is back to {}%",
+                            previous.display_name, account.email, current.percentage
+                        ),
+                    );
+                }
+            }
+        }
+
+        crate::tray::emit_accounts_updated(app_handle);
+    }
+}
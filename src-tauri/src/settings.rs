@@ -0,0 +1,715 @@
+// Application settings - persisted next to accounts.json
+
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::account;
+use crate::catalog::ModelCatalog;
+
+/// Event emitted with the new settings whenever they are saved
+pub const SETTINGS_CHANGED: &str = "settings-changed";
+
+/// Application settings
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    pub auto_switch: AutoSwitchPolicy,
+    pub quota_thresholds: QuotaThresholds,
+    pub installation: InstallationSettings,
+    pub targets: Vec<IdeTarget>,
+    /// Named target the tray and shortcuts switch; the default IDE when unset
+    pub tray_target: Option<String>,
+    pub switch_mode: SwitchMode,
+    pub notifications: NotificationSettings,
+    pub shortcuts: ShortcutSettings,
+    pub oauth: OAuthSettings,
+    pub process: ProcessSettings,
+}
+
+impl Settings {
+    /// Check every section, naming the first value that is not usable
+    pub fn validate(&self) -> anyhow::Result<()> {
+        self.auto_switch.validate()?;
+        self.quota_thresholds.validate()?;
+        self.installation.validate()?;
+        validate_targets(&self.targets)?;
+        if let Some(tray_target) = &self.tray_target {
+            if !self.targets.iter().any(|t| &t.name == tray_target) {
+                anyhow::bail!("Tray target {} is not an IDE target", tray_target);
+            }
+        }
+        self.shortcuts.validate()?;
+        self.oauth.validate()?;
+        self.process.validate()
+    }
+
+    /// Bring values saved by older versions or edited by hand into range
+    ///
+    /// Each fix is logged; the file itself is only rewritten by the next save.
+    fn repair(&mut self) {
+        let policy = &mut self.auto_switch;
+        if let Err(e) = policy.validate() {
+            eprintln!("Warning: {}, clamping auto-switch settings", e);
+            policy.threshold_percent = policy.threshold_percent.clamp(0, 100);
+            policy.check_interval_minutes = policy.check_interval_minutes.max(1);
+            if let Some(quiet_hours) = &mut policy.quiet_hours {
+                quiet_hours.start_hour = quiet_hours.start_hour.min(23);
+                quiet_hours.end_hour = quiet_hours.end_hour.min(23);
+            }
+        }
+
+        if let Err(e) = self.quota_thresholds.validate() {
+            eprintln!("Warning: {}, clamping quota thresholds", e);
+            let thresholds = &mut self.quota_thresholds;
+            let levels = std::iter::once(&mut thresholds.default)
+                .chain(thresholds.per_model.values_mut())
+                .chain(thresholds.per_account.values_mut().flat_map(|models| models.values_mut()));
+            for levels in levels {
+                levels.warning = levels.warning.clamp(0, 100);
+                levels.critical = levels.critical.clamp(0, levels.warning);
+            }
+        }
+
+        if let Err(e) = self.installation.validate() {
+            eprintln!("Warning: {}, giving duplicates new ids", e);
+            let mut ids = HashSet::new();
+            for custom in &mut self.installation.custom {
+                if !ids.insert(custom.id.clone()) {
+                    custom.id.clear();
+                }
+            }
+            self.installation.assign_ids();
+        }
+
+        if let Err(e) = validate_targets(&self.targets) {
+            eprintln!("Warning: {}, ignoring invalid IDE targets", e);
+            let mut names = HashSet::new();
+            self.targets
+                .retain(|t| !t.name.trim().is_empty() && names.insert(t.name.clone()));
+        }
+        if let Some(tray_target) = &self.tray_target {
+            if !self.targets.iter().any(|t| &t.name == tray_target) {
+                eprintln!("Warning: Tray target {} is not an IDE target, using the default IDE", tray_target);
+                self.tray_target = None;
+            }
+        }
+
+        if let Err(e) = self.shortcuts.validate() {
+            eprintln!("Warning: {}, unassigning the duplicate", e);
+            let mut accelerators = HashSet::new();
+            for accelerator in self.shortcuts.accelerators_mut() {
+                if let Some(a) = accelerator {
+                    if !a.trim().is_empty() && !accelerators.insert(a.to_lowercase()) {
+                        *accelerator = None;
+                    }
+                }
+            }
+        }
+
+        if let Err(e) = self.oauth.validate() {
+            eprintln!("Warning: {}, using defaults for invalid OAuth settings", e);
+            if self.oauth.callback_port < 1024 {
+                self.oauth.callback_port = OAuthSettings::default().callback_port;
+            }
+            self.oauth.timeout_minutes = self.oauth.timeout_minutes.clamp(1, 60);
+        }
+
+        if let Err(e) = self.process.validate() {
+            eprintln!("Warning: {}, clamping process timeouts", e);
+            for secs in [
+                &mut self.process.shutdown_timeout_secs,
+                &mut self.process.kill_timeout_secs,
+                &mut self.process.launch_timeout_secs,
+            ] {
+                *secs = (*secs).clamp(1, 300);
+            }
+        }
+    }
+}
+
+/// Check that IDE target names are set and unique
+pub fn validate_targets(targets: &[IdeTarget]) -> anyhow::Result<()> {
+    let mut names = HashSet::new();
+    for target in targets {
+        if target.name.trim().is_empty() {
+            anyhow::bail!("IDE target names cannot be empty");
+        }
+        if !names.insert(target.name.as_str()) {
+            anyhow::bail!("Duplicate IDE target name: {}", target.name);
+        }
+    }
+    Ok(())
+}
+
+/// Local server that receives the OAuth redirect
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct OAuthSettings {
+    /// Port of `http://localhost:<port>/auth/callback`, which must be an
+    /// authorized redirect URI of the OAuth client
+    pub callback_port: u16,
+    /// How long to wait for the browser sign-in
+    pub timeout_minutes: u32,
+}
+
+impl OAuthSettings {
+    pub fn validate(&self) -> anyhow::Result<()> {
+        if self.callback_port < 1024 {
+            anyhow::bail!("OAuth callback port must be 1024 or above");
+        }
+        if !(1..=60).contains(&self.timeout_minutes) {
+            anyhow::bail!("OAuth timeout must be between 1 and 60 minutes");
+        }
+        Ok(())
+    }
+}
+
+impl Default for OAuthSettings {
+    fn default() -> Self {
+        OAuthSettings {
+            callback_port: 3847,
+            timeout_minutes: 5,
+        }
+    }
+}
+
+/// How long the IDE gets to stop and start during a switch
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ProcessSettings {
+    /// Time to exit after a polite shutdown request before being killed (Linux)
+    pub shutdown_timeout_secs: u64,
+    /// Time to disappear after being killed
+    pub kill_timeout_secs: u64,
+    /// Time to show up after being relaunched
+    pub launch_timeout_secs: u64,
+}
+
+impl ProcessSettings {
+    pub fn shutdown_timeout(&self) -> Duration {
+        Duration::from_secs(self.shutdown_timeout_secs)
+    }
+
+    pub fn kill_timeout(&self) -> Duration {
+        Duration::from_secs(self.kill_timeout_secs)
+    }
+
+    pub fn launch_timeout(&self) -> Duration {
+        Duration::from_secs(self.launch_timeout_secs)
+    }
+
+    pub fn validate(&self) -> anyhow::Result<()> {
+        for (name, secs) in [
+            ("Shutdown timeout", self.shutdown_timeout_secs),
+            ("Kill timeout", self.kill_timeout_secs),
+            ("Launch timeout", self.launch_timeout_secs),
+        ] {
+            if !(1..=300).contains(&secs) {
+                anyhow::bail!("{} must be between 1 and 300 seconds", name);
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Default for ProcessSettings {
+    fn default() -> Self {
+        ProcessSettings {
+            shutdown_timeout_secs: 5,
+            kill_timeout_secs: 5,
+            launch_timeout_secs: 15,
+        }
+    }
+}
+
+/// What a switch does with the IDE process
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SwitchMode {
+    /// Only write the database; fails if the IDE is running
+    InjectOnly,
+    /// Stop and relaunch the IDE only if it was running
+    #[default]
+    RestartIfRunning,
+    /// Stop the IDE if it is running and launch it afterwards either way
+    AlwaysLaunch,
+}
+
+/// Policy for switching away from the active account when its quota runs low
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AutoSwitchPolicy {
+    pub enabled: bool,
+    /// Model name or display name to watch
+    pub model: String,
+    /// Switch when the remaining percentage falls below this value
+    pub threshold_percent: i32,
+    /// Minimum time between two automatic switches
+    pub cooldown_minutes: u32,
+    /// How often the active account's quota is checked
+    pub check_interval_minutes: u32,
+    pub quiet_hours: Option<QuietHours>,
+    pub notify: bool,
+}
+
+impl AutoSwitchPolicy {
+    pub fn validate(&self) -> anyhow::Result<()> {
+        if !(0..=100).contains(&self.threshold_percent) {
+            anyhow::bail!("Auto-switch threshold must be between 0 and 100%");
+        }
+        if self.check_interval_minutes == 0 {
+            anyhow::bail!("Auto-switch check interval must be at least 1 minute");
+        }
+        if let Some(quiet_hours) = &self.quiet_hours {
+            if quiet_hours.start_hour > 23 || quiet_hours.end_hour > 23 {
+                anyhow::bail!("Quiet hours must be between 0 and 23");
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Default for AutoSwitchPolicy {
+    fn default() -> Self {
+        AutoSwitchPolicy {
+            enabled: false,
+            model: "Claude Sonnet 4.5".to_string(),
+            threshold_percent: 10,
+            cooldown_minutes: 30,
+            check_interval_minutes: 5,
+            quiet_hours: None,
+            notify: true,
+        }
+    }
+}
+
+/// Which desktop notifications are shown, and how often
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NotificationSettings {
+    /// Master switch for all notifications
+    pub enabled: bool,
+    /// Result of switches started from the tray or by auto-switch
+    pub switch_result: bool,
+    /// A model's quota dropping to its warning, critical or exhausted level
+    pub low_quota: bool,
+    /// A model's quota coming back after its reset time
+    pub quota_reset: bool,
+    /// An account's refresh token being revoked
+    pub auth_revoked: bool,
+    /// Minimum time before the same background notification is shown again
+    pub min_interval_minutes: u32,
+}
+
+impl Default for NotificationSettings {
+    fn default() -> Self {
+        NotificationSettings {
+            enabled: true,
+            switch_result: true,
+            low_quota: true,
+            quota_reset: true,
+            auth_revoked: true,
+            min_interval_minutes: 60,
+        }
+    }
+}
+
+/// System-wide keyboard shortcuts, as accelerators like "CmdOrCtrl+Shift+Alt+N"
+///
+/// An unset accelerator leaves that action without a shortcut. Shortcuts are
+/// off until enabled, since a stray key press would restart the IDE.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ShortcutSettings {
+    pub enabled: bool,
+    /// Switch to the account after the active one
+    pub next_account: Option<String>,
+    /// Switch to the account with the most quota for the auto-switch model
+    pub best_account: Option<String>,
+    /// Bring up the account list
+    pub show_switcher: Option<String>,
+}
+
+impl ShortcutSettings {
+    /// Every accelerator slot, set or not
+    fn accelerators_mut(&mut self) -> [&mut Option<String>; 3] {
+        [&mut self.next_account, &mut self.best_account, &mut self.show_switcher]
+    }
+
+    pub fn validate(&self) -> anyhow::Result<()> {
+        let shortcuts = [&self.next_account, &self.best_account, &self.show_switcher];
+        let mut accelerators = HashSet::new();
+        for accelerator in shortcuts.into_iter().flatten() {
+            if !accelerator.trim().is_empty() && !accelerators.insert(accelerator.to_lowercase()) {
+                anyhow::bail!("Shortcut {} is assigned twice", accelerator);
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Default for ShortcutSettings {
+    fn default() -> Self {
+        ShortcutSettings {
+            enabled: false,
+            next_account: Some("CmdOrCtrl+Shift+Alt+N".to_string()),
+            best_account: Some("CmdOrCtrl+Shift+Alt+B".to_string()),
+            show_switcher: Some("CmdOrCtrl+Shift+Alt+S".to_string()),
+        }
+    }
+}
+
+/// Local hours (0-23) during which no automatic switch happens
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QuietHours {
+    pub start_hour: u32,
+    pub end_hour: u32,
+}
+
+impl QuietHours {
+    /// Check whether the given local hour falls inside the quiet period
+    pub fn contains(&self, hour: u32) -> bool {
+        if self.start_hour <= self.end_hour {
+            hour >= self.start_hour && hour < self.end_hour
+        } else {
+            // Wraps around midnight, e.g. 22 -> 7
+            hour >= self.start_hour || hour < self.end_hour
+        }
+    }
+}
+
+/// Remaining-percentage levels at which a model's quota is flagged
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct ThresholdLevels {
+    pub warning: i32,
+    pub critical: i32,
+}
+
+/// Low-quota thresholds, from most to least specific:
+/// per account and model, per model, the model catalog, then the default
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct QuotaThresholds {
+    pub default: ThresholdLevels,
+    /// Keyed by model name or display name
+    pub per_model: HashMap<String, ThresholdLevels>,
+    /// Keyed by account id, then by model name or display name
+    pub per_account: HashMap<String, HashMap<String, ThresholdLevels>>,
+}
+
+impl Default for QuotaThresholds {
+    fn default() -> Self {
+        QuotaThresholds {
+            default: ThresholdLevels {
+                warning: 20,
+                critical: 5,
+            },
+            per_model: HashMap::new(),
+            per_account: HashMap::new(),
+        }
+    }
+}
+
+impl QuotaThresholds {
+    pub fn validate(&self) -> anyhow::Result<()> {
+        let levels = std::iter::once(("default", &self.default))
+            .chain(self.per_model.iter().map(|(model, levels)| (model.as_str(), levels)))
+            .chain(
+                self.per_account
+                    .values()
+                    .flat_map(|models| models.iter().map(|(model, levels)| (model.as_str(), levels))),
+            );
+        for (name, levels) in levels {
+            if !(0..=100).contains(&levels.critical) || !(0..=100).contains(&levels.warning) {
+                anyhow::bail!("Quota thresholds for {} must be between 0 and 100%", name);
+            }
+            if levels.critical > levels.warning {
+                anyhow::bail!("Critical quota threshold for {} is above its warning threshold", name);
+            }
+        }
+        Ok(())
+    }
+
+    /// Resolve the thresholds that apply to a model on an account
+    pub fn resolve(
+        &self,
+        catalog: &ModelCatalog,
+        account_id: &str,
+        name: &str,
+        display_name: &str,
+    ) -> ThresholdLevels {
+        let lookup = |map: &HashMap<String, ThresholdLevels>| {
+            map.get(name).or_else(|| map.get(display_name)).copied()
+        };
+
+        if let Some(levels) = self.per_account.get(account_id).and_then(lookup) {
+            return levels;
+        }
+
+        if let Some(levels) = lookup(&self.per_model) {
+            return levels;
+        }
+
+        match catalog.lookup(name).and_then(|entry| entry.low_quota_threshold) {
+            Some(critical) => ThresholdLevels {
+                warning: self.default.warning.max(critical),
+                critical,
+            },
+            None => self.default,
+        }
+    }
+}
+
+/// Which IDE installation to switch, plus user-configured installations
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct InstallationSettings {
+    /// Installation id; auto-detected when unset
+    pub selected: Option<String>,
+    pub custom: Vec<CustomInstallation>,
+}
+
+impl InstallationSettings {
+    /// Give new custom installations an id, returning whether any was missing
+    ///
+    /// Installations are selected by id, so it must survive reordering and
+    /// deleting other entries. A selection saved by position ("custom:<index>")
+    /// before ids existed is moved to the id of that entry.
+    pub fn assign_ids(&mut self) -> bool {
+        let mut changed = false;
+        for (index, custom) in self.custom.iter_mut().enumerate() {
+            if !custom.id.is_empty() {
+                continue;
+            }
+            custom.id = Uuid::new_v4().to_string();
+            changed = true;
+
+            if self.selected.as_deref() == Some(format!("custom:{}", index).as_str()) {
+                self.selected = Some(format!("custom:{}", custom.id));
+            }
+        }
+        changed
+    }
+
+    pub fn validate(&self) -> anyhow::Result<()> {
+        let mut ids = HashSet::new();
+        for custom in &self.custom {
+            if !custom.id.is_empty() && !ids.insert(custom.id.as_str()) {
+                anyhow::bail!("Duplicate custom installation id: {}", custom.id);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// An installation the detector cannot find on its own
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CustomInstallation {
+    /// Stable id, generated when the entry is first saved
+    #[serde(default)]
+    pub id: String,
+    pub label: String,
+    /// Directory holding `User/globalStorage/state.vscdb`
+    pub user_data_dir: PathBuf,
+    /// Program and arguments used to start the IDE
+    pub launcher: Vec<String>,
+}
+
+/// A named IDE profile started with its own `--user-data-dir`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IdeTarget {
+    pub name: String,
+    pub user_data_dir: PathBuf,
+    /// Program and arguments used to start this profile; when empty the selected
+    /// installation's launcher is used with `--user-data-dir` appended
+    #[serde(default)]
+    pub launcher: Vec<String>,
+}
+
+/// Get the settings file path
+fn get_settings_path() -> anyhow::Result<PathBuf> {
+    Ok(account::get_data_dir()?.join("settings.json"))
+}
+
+/// Load settings from storage, falling back to defaults
+///
+/// Nothing is written here; commands load, change and save settings, and a
+/// write from a plain read could overwrite a save in between.
+pub fn load_settings() -> anyhow::Result<Settings> {
+    let path = get_settings_path()?;
+    if !path.exists() {
+        return Ok(Settings::default());
+    }
+
+    let content = fs::read_to_string(&path)?;
+    Ok(parse_settings(&content))
+}
+
+/// Parse a settings file, using defaults if it is not valid JSON
+fn parse_settings(content: &str) -> Settings {
+    let mut settings: Settings = match serde_json::from_str(content) {
+        Ok(settings) => settings,
+        Err(e) => {
+            eprintln!("Warning: Cannot parse settings.json, using defaults: {}", e);
+            return Settings::default();
+        }
+    };
+    settings.installation.assign_ids();
+    settings.repair();
+    settings
+}
+
+/// Give custom installations saved without an id a stable one
+///
+/// Called once at startup, before any command can save settings, so ids
+/// generated in memory by a later load never differ from the saved ones.
+pub fn migrate_settings() -> anyhow::Result<()> {
+    let path = get_settings_path()?;
+    if !path.exists() {
+        return Ok(());
+    }
+
+    let content = fs::read_to_string(&path)?;
+    let Ok(mut settings) = serde_json::from_str::<Settings>(&content) else {
+        return Ok(());
+    };
+    if settings.installation.assign_ids() {
+        save_settings(&settings)?;
+    }
+    Ok(())
+}
+
+/// Save settings to storage
+pub fn save_settings(settings: &Settings) -> anyhow::Result<()> {
+    let path = get_settings_path()?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(&path, serde_json::to_string_pretty(settings)?)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bad_json_loads_defaults() {
+        for content in ["", "{", "not json", "[1, 2]", r#"{"auto_switch": {"threshold_percent": "ten"}}"#] {
+            let settings = parse_settings(content);
+            assert!(!settings.auto_switch.enabled);
+            assert_eq!(settings.switch_mode, SwitchMode::default());
+            assert!(settings.targets.is_empty());
+        }
+    }
+
+    #[test]
+    fn missing_sections_use_defaults() {
+        let settings = parse_settings(r#"{"tray_target": null, "process": {"kill_timeout_secs": 9}}"#);
+        assert_eq!(settings.process.kill_timeout_secs, 9);
+        assert_eq!(settings.process.launch_timeout_secs, ProcessSettings::default().launch_timeout_secs);
+        assert!(settings.validate().is_ok());
+    }
+
+    #[test]
+    fn defaults_are_valid() {
+        assert!(Settings::default().validate().is_ok());
+    }
+
+    #[test]
+    fn validate_names_invalid_values() {
+        let mut settings = Settings::default();
+        settings.auto_switch.threshold_percent = 150;
+        assert!(settings.validate().is_err());
+
+        let mut settings = Settings::default();
+        settings.quota_thresholds.default.critical = 30;
+        assert!(settings.validate().is_err());
+
+        let settings = Settings {
+            tray_target: Some("work".to_string()),
+            ..Settings::default()
+        };
+        assert!(settings.validate().is_err());
+
+        let mut settings = Settings::default();
+        settings.shortcuts.best_account = settings.shortcuts.next_account.clone();
+        assert!(settings.validate().is_err());
+
+        let mut settings = Settings::default();
+        settings.process.kill_timeout_secs = 0;
+        assert!(settings.validate().is_err());
+    }
+
+    #[test]
+    fn repair_makes_settings_valid() {
+        // Seeded values in and out of every range
+        let mut seed: u64 = 0x5eed;
+        let mut next = |range: i64| {
+            seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+            ((seed >> 33) as i64 % range) - range / 2
+        };
+
+        for _ in 0..500 {
+            let mut settings = Settings::default();
+            settings.auto_switch.threshold_percent = next(400) as i32;
+            settings.auto_switch.check_interval_minutes = next(4).unsigned_abs() as u32;
+            settings.auto_switch.quiet_hours = Some(QuietHours {
+                start_hour: next(60).unsigned_abs() as u32,
+                end_hour: next(60).unsigned_abs() as u32,
+            });
+            settings.quota_thresholds.default = ThresholdLevels {
+                warning: next(400) as i32,
+                critical: next(400) as i32,
+            };
+            settings.targets = vec![
+                IdeTarget {
+                    name: if next(2) == 0 { String::new() } else { "work".to_string() },
+                    user_data_dir: PathBuf::from("/a"),
+                    launcher: Vec::new(),
+                },
+                IdeTarget {
+                    name: "work".to_string(),
+                    user_data_dir: PathBuf::from("/b"),
+                    launcher: Vec::new(),
+                },
+            ];
+            settings.tray_target = Some(if next(2) == 0 { "home" } else { "work" }.to_string());
+            settings.shortcuts.show_switcher = settings.shortcuts.next_account.clone();
+            settings.oauth.callback_port = next(4000).unsigned_abs() as u16;
+            settings.oauth.timeout_minutes = next(200).unsigned_abs() as u32;
+            settings.process.shutdown_timeout_secs = next(1000).unsigned_abs();
+            settings.installation.custom = vec![custom_installation("x"), custom_installation("x")];
+
+            settings.repair();
+            assert!(settings.validate().is_ok(), "{:?}", settings);
+        }
+    }
+
+    #[test]
+    fn assign_ids_moves_positional_selection() {
+        let mut installation = InstallationSettings {
+            selected: Some("custom:1".to_string()),
+            custom: vec![custom_installation("a"), custom_installation("")],
+        };
+        assert!(installation.assign_ids());
+        let id = installation.custom[1].id.clone();
+        assert!(!id.is_empty());
+        assert_eq!(installation.selected, Some(format!("custom:{}", id)));
+        assert!(!installation.assign_ids());
+    }
+
+    fn custom_installation(id: &str) -> CustomInstallation {
+        CustomInstallation {
+            id: id.to_string(),
+            label: "Custom".to_string(),
+            user_data_dir: PathBuf::from("/custom"),
+            launcher: Vec::new(),
+        }
+    }
+}
@@ -0,0 +1,164 @@
+// Global shortcuts - switch accounts without opening the window
+
+use tauri::{AppHandle, GlobalShortcutManager, Manager};
+
+use crate::audit::Trigger;
+use crate::notify::NotificationCategory;
+use crate::recommend;
+use crate::settings::{self, ShortcutSettings};
+use crate::tray;
+use crate::AppState;
+
+/// Event asking the frontend to show the account list
+pub const SHOW_SWITCHER: &str = "show-switcher";
+
+/// What a shortcut does
+#[derive(Debug, Clone, Copy)]
+enum ShortcutAction {
+    NextAccount,
+    BestAccount,
+    ShowSwitcher,
+}
+
+/// Register the configured shortcuts, replacing any registered before
+///
+/// Every shortcut that can be registered is; the error lists the ones that
+/// could not, e.g. because the accelerator is invalid or taken by another app.
+pub fn register(app: &AppHandle) -> anyhow::Result<()> {
+    let mut manager = app.global_shortcut_manager();
+    manager.unregister_all()?;
+
+    let shortcuts = settings::load_settings()?.shortcuts;
+    if !shortcuts.enabled {
+        return Ok(());
+    }
+
+    let mut failures = Vec::new();
+    for (accelerator, action) in bindings(&shortcuts) {
+        let handle = app.clone();
+        if let Err(e) = manager.register(accelerator, move || run(&handle, action)) {
+            failures.push(format!("{} ({})", accelerator, e));
+        }
+    }
+
+    if !failures.is_empty() {
+        anyhow::bail!("Could not register shortcuts: {}", failures.join(", "));
+    }
+    Ok(())
+}
+
+/// The configured accelerator of each action that has one
+fn bindings(shortcuts: &ShortcutSettings) -> Vec<(&str, ShortcutAction)> {
+    [
+        (&shortcuts.next_account, ShortcutAction::NextAccount),
+        (&shortcuts.best_account, ShortcutAction::BestAccount),
+        (&shortcuts.show_switcher, ShortcutAction::ShowSwitcher),
+    ]
+    .into_iter()
+    .filter_map(|(accelerator, action)| {
+        accelerator
+            .as_deref()
+            .filter(|a| !a.trim().is_empty())
+            .map(|a| (a, action))
+    })
+    .collect()
+}
+
+/// Run a shortcut's action
+fn run(app: &AppHandle, action: ShortcutAction) {
+    match action {
+        ShortcutAction::NextAccount => switch_to_next(app),
+        ShortcutAction::BestAccount => switch_to_best(app),
+        ShortcutAction::ShowSwitcher => {
+            tray::show_main_window(app);
+            let _ = app.emit_all(SHOW_SWITCHER, ());
+        }
+    }
+}
+
+/// Switch to the account after the active one, wrapping around and skipping revoked ones
+fn switch_to_next(app: &AppHandle) {
+    let state = app.state::<AppState>();
+    // The active account is stale while a switch runs, so a quick second press
+    // would pick the same account again
+    if state.switch_lock.try_lock().is_err() {
+        state.notifier.notify(
+            NotificationCategory::SwitchResult,
+            "",
+            "Switch in progress",
+            "Wait for the current switch to finish",
+        );
+        return;
+    }
+
+    let accounts = {
+        let manager = state.account_manager.lock().unwrap();
+        manager.load_accounts().unwrap_or_default()
+    };
+
+    let target = tray::switch_target();
+    let active = accounts
+        .iter()
+        .position(|a| a.is_active_in(target.as_deref()))
+        .unwrap_or(accounts.len().saturating_sub(1));
+    let next = (1..accounts.len())
+        .map(|offset| &accounts[(active + offset) % accounts.len()])
+        .find(|a| !a.auth_revoked);
+
+    match next {
+        Some(next) => crate::spawn_switch(app, next.id.clone(), Trigger::Shortcut),
+        None => {
+            state.notifier.notify(
+                NotificationCategory::SwitchResult,
+                "",
+                "Nothing to switch to",
+                "There is no other usable account",
+            );
+        }
+    }
+}
+
+/// Switch to the account with the most quota for the auto-switch model
+fn switch_to_best(app: &AppHandle) {
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        let state = app.state::<AppState>();
+        let accounts = {
+            let manager = state.account_manager.lock().unwrap();
+            manager.load_accounts().unwrap_or_default()
+        };
+        let model = settings::load_settings()
+            .map(|s| s.auto_switch.model)
+            .unwrap_or_default();
+        let target = tray::switch_target();
+        let active_id = accounts
+            .iter()
+            .find(|a| a.is_active_in(target.as_deref()))
+            .map(|a| a.id.clone());
+
+        let candidates = crate::collect_quotas(&app, accounts, Trigger::Shortcut).await;
+        let best = recommend::rank_accounts(&candidates, &model)
+            .into_iter()
+            .find(|r| !r.auth_revoked);
+
+        match best {
+            Some(best) if active_id.as_ref() != Some(&best.account_id) => crate::spawn_switch(&app, best.account_id, Trigger::Shortcut),
+            Some(best) => {
+                state.notifier.notify(
+                    NotificationCategory::SwitchResult,
+                    &best.account_id,
+                    "Already on the best account",
+                    &format!("{} has the most {} quota", best.email, model),
+                );
+            }
+            None => {
+                state.notifier.notify(
+                    NotificationCategory::SwitchResult,
+                    "",
+                    "Nothing to switch to",
+                    "There is no usable account",
+                );
+            }
+        }
+    });
+}
@@ -0,0 +1,140 @@
+// Per-account IDE state snapshots - chat threads and assistant state survive switches
+
+use std::fs;
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+
+use crate::account;
+use crate::db;
+use crate::install::Installation;
+use crate::recipe::SwitchRecipe;
+
+/// The assistant state of one account in one IDE target
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Snapshot {
+    pub account_id: String,
+    pub taken_at: i64,
+    pub entries: Vec<SnapshotEntry>,
+}
+
+/// A saved `ItemTable` row
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SnapshotEntry {
+    pub key: String,
+    /// SQLite storage class of the value: "text", "blob", "integer" or "real"
+    pub value_type: String,
+    /// The value's bytes, hex-encoded so blobs survive the round trip
+    pub hex: String,
+}
+
+impl SnapshotEntry {
+    /// Size of the value in bytes
+    pub fn size(&self) -> usize {
+        self.hex.len() / 2
+    }
+
+    /// SQL expression that recreates the value with its original storage class
+    fn sql_value(&self) -> String {
+        let bytes = format!("X'{}'", self.hex);
+        match self.value_type.as_str() {
+            "blob" => bytes,
+            "integer" => format!("CAST(CAST({} AS TEXT) AS INTEGER)", bytes),
+            "real" => format!("CAST(CAST({} AS TEXT) AS REAL)", bytes),
+            _ => format!("CAST({} AS TEXT)", bytes),
+        }
+    }
+}
+
+/// Get the snapshot file for an account in a target
+fn get_snapshot_path(target: &Installation, account_id: &str) -> anyhow::Result<PathBuf> {
+    Ok(account::get_data_dir()?
+        .join("snapshots")
+        .join(target.slug())
+        .join(format!("{}.json", account_id)))
+}
+
+/// Save the account's chat threads and assistant state from the target's database
+///
+/// Saves exactly the keys the recipe clears. The database must be closed.
+/// Returns the number of keys saved.
+pub fn save_snapshot(target: &Installation, recipe: &SwitchRecipe, account_id: &str) -> anyhow::Result<usize> {
+    let db_path = target.db_path();
+    if !db_path.exists() {
+        return Ok(0);
+    }
+
+    let output = db::execute_query(
+        &db_path,
+        &format!(
+            "SELECT key, typeof(value), hex(value) FROM ItemTable WHERE {};",
+            recipe.clear_condition()
+        ),
+    )?;
+
+    let entries: Vec<SnapshotEntry> = output
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.rsplitn(3, '|');
+            let hex = fields.next()?;
+            let value_type = fields.next()?;
+            let key = fields.next()?;
+            (value_type != "null").then(|| SnapshotEntry {
+                key: key.to_string(),
+                value_type: value_type.to_string(),
+                hex: hex.to_string(),
+            })
+        })
+        .collect();
+
+    let snapshot = Snapshot {
+        account_id: account_id.to_string(),
+        taken_at: chrono::Utc::now().timestamp_millis(),
+        entries,
+    };
+
+    let path = get_snapshot_path(target, account_id)?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(&path, serde_json::to_string_pretty(&snapshot)?)?;
+
+    Ok(snapshot.entries.len())
+}
+
+/// Load an account's snapshot for a target, if one was taken
+pub fn load_snapshot(target: &Installation, account_id: &str) -> anyhow::Result<Option<Snapshot>> {
+    let path = get_snapshot_path(target, account_id)?;
+    if !path.exists() {
+        return Ok(None);
+    }
+
+    let content = fs::read_to_string(&path)?;
+    Ok(Some(serde_json::from_str(&content)?))
+}
+
+/// Write an account's snapshot back into the target's database
+///
+/// Returns the number of keys restored, or `None` if the account has no snapshot.
+pub fn restore_snapshot(target: &Installation, account_id: &str) -> anyhow::Result<Option<usize>> {
+    let snapshot = match load_snapshot(target, account_id)? {
+        Some(snapshot) => snapshot,
+        None => return Ok(None),
+    };
+    if snapshot.entries.is_empty() {
+        return Ok(Some(0));
+    }
+
+    let mut script = String::from("BEGIN;\n");
+    for entry in &snapshot.entries {
+        script.push_str(&format!(
+            "INSERT OR REPLACE INTO ItemTable (key, value) VALUES ({}, {});\n",
+            db::quote(&entry.key),
+            entry.sql_value()
+        ));
+    }
+    script.push_str("COMMIT;\n");
+
+    db::execute_script(&target.db_path(), &script)?;
+    Ok(Some(snapshot.entries.len()))
+}
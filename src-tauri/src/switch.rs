@@ -1,321 +1,773 @@
 // Account switching - database injection and process management
 
 use std::fs;
-use std::path::PathBuf;
-use std::process::Command;
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, Stdio};
+use std::time::Duration;
 
-/// Kill Antigravity processes
-pub async fn kill_antigravity() -> anyhow::Result<()> {
-    #[cfg(target_os = "macos")]
-    {
-        let _ = Command::new("pkill")
-            .args(["-9", "-i", "Antigravity"])
-            .output();
-        let _ = Command::new("pkill")
-            .args(["-9", "-f", "Antigravity Helper"])
-            .output();
+use serde::Serialize;
+
+use crate::account;
+use crate::codec::{self, OAuthFields};
+use crate::db;
+use crate::install::Installation;
+use crate::oauth::TokenExpiry;
+use crate::process;
+use crate::recipe::{self, SwitchRecipe, ValueEncoding};
+use crate::settings;
+
+/// How often the relaunched IDE is looked for
+const LAUNCH_POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Number of database backups kept in the backup directory
+const MAX_BACKUPS: usize = 10;
+
+/// Oldest IDE version the injection format has been verified with
+const MIN_SUPPORTED_VERSION: &str = "1.11.0";
+
+/// First IDE version the injection format has not been verified with
+const MAX_SUPPORTED_VERSION: &str = "2.0.0";
+
+/// Whether the injection format is known to work with an IDE version
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum VersionStatus {
+    Supported,
+    /// The version could not be detected
+    Unknown,
+    /// The version is outside the verified range
+    Unsupported,
+}
+
+/// Result of checking an IDE version against the supported range
+#[derive(Debug, Clone, Serialize)]
+pub struct VersionCheck {
+    pub version: Option<String>,
+    pub status: VersionStatus,
+    pub supported_range: String,
+    pub message: Option<String>,
+}
+
+/// Check an IDE version against the supported range
+///
+/// A recipe that names its own version range vouches for the versions in it,
+/// so user recipes can extend support without a new release.
+pub fn check_version(version: Option<&str>, recipe: Option<&SwitchRecipe>) -> VersionCheck {
+    let declared = recipe.filter(|r| (r.min_version.is_some() || r.max_version.is_some()) && r.matches(version));
+    let supported_range = match declared {
+        Some(r) => format!(
+            "{} to {} (recipe {})",
+            r.min_version.as_deref().unwrap_or("any"),
+            r.max_version.as_deref().unwrap_or("any"),
+            r.name
+        ),
+        None => format!("{} to {}", MIN_SUPPORTED_VERSION, MAX_SUPPORTED_VERSION),
+    };
+    
+    let (status, message) = match version {
+        _ if declared.is_some() => (VersionStatus::Supported, None),
+        None => (
+            VersionStatus::Unknown,
+            Some("Could not detect the Antigravity version - the injected data may not match its schema".to_string()),
+        ),
+        Some(v) if recipe::compare_versions(v, MIN_SUPPORTED_VERSION).is_lt()
+            || recipe::compare_versions(v, MAX_SUPPORTED_VERSION).is_ge() =>
+        {
+            (
+                VersionStatus::Unsupported,
+                Some(format!(
+                    "Antigravity {} is outside the supported range {} - switching would write an unknown schema",
+                    v, supported_range
+                )),
+            )
+        }
+        Some(_) => (VersionStatus::Supported, None),
+    };
+    
+    VersionCheck {
+        version: version.map(|v| v.to_string()),
+        status,
+        supported_range,
+        message,
     }
+}
+
+/// The account credentials written into the IDE
+#[derive(Debug, Clone, Copy)]
+pub struct Credentials<'a> {
+    pub access_token: &'a str,
+    pub refresh_token: &'a str,
+    pub expiry: TokenExpiry,
+    pub email: &'a str,
+}
+
+/// How the IDE was running before it was stopped, so it can be relaunched the same way
+#[derive(Debug, Clone)]
+pub struct LaunchContext {
+    pub cmdline: Vec<String>,
+    pub cwd: Option<PathBuf>,
+}
+
+/// Kill the target's Antigravity processes and wait until they have exited
+///
+/// On Linux only the processes using the target's user-data dir are stopped,
+/// and the main process's command line and working directory are returned;
+/// on macOS and Windows every Antigravity instance is stopped and nothing is
+/// recorded.
+pub async fn kill_antigravity(target: &Installation) -> anyhow::Result<Option<LaunchContext>> {
+    let timeouts = settings::load_settings().map(|s| s.process).unwrap_or_default();
     
-    #[cfg(target_os = "windows")]
+    #[cfg(not(target_os = "linux"))]
     {
-        let _ = Command::new("taskkill")
-            .args(["/F", "/IM", "Antigravity.exe", "/T"])
-            .output();
+        let _ = target;
+        for command_line in kill_commands() {
+            let _ = Command::new(command_line[0]).args(&command_line[1..]).output();
+        }
     }
     
     #[cfg(target_os = "linux")]
-    {
-        let _ = Command::new("pkill")
-            .args(["-9", "-f", "antigravity"])
-            .output();
-    }
+    let context = {
+        let processes = target_processes(target);
+        let context = launch_context(&processes);
+        let pids: Vec<u32> = processes.iter().map(|p| p.pid).collect();
+        
+        // Ask nicely first so the IDE can save its state
+        process::signal(&pids, "TERM");
+        let survivors = process::wait_for_exit(&pids, timeouts.shutdown_timeout()).await;
+        
+        if !survivors.is_empty() {
+            eprintln!("Warning: {} Antigravity process(es) ignored SIGTERM, sending SIGKILL", survivors.len());
+            process::signal(&survivors, "KILL");
+            
+            if !process::wait_for_exit(&survivors, timeouts.kill_timeout()).await.is_empty() {
+                anyhow::bail!("Antigravity is still running after being killed");
+            }
+        }
+        
+        context
+    };
     
-    Ok(())
+    #[cfg(not(target_os = "linux"))]
+    let context = {
+        if !process::wait_until_stopped(timeouts.kill_timeout()).await {
+            anyhow::bail!("Antigravity is still running after being killed");
+        }
+        None
+    };
+    
+    Ok(context)
 }
 
-/// Restart Antigravity
-pub async fn restart_antigravity() -> anyhow::Result<()> {
+/// Commands that kill every Antigravity instance where processes cannot be matched to a target
+#[cfg(not(target_os = "linux"))]
+fn kill_commands() -> Vec<Vec<&'static str>> {
     #[cfg(target_os = "macos")]
     {
-        Command::new("open")
-            .args(["-a", "Antigravity"])
-            .spawn()?;
+        vec![
+            vec!["pkill", "-9", "-i", "Antigravity"],
+            vec!["pkill", "-9", "-f", "Antigravity Helper"],
+        ]
     }
     
     #[cfg(target_os = "windows")]
     {
-        let home_dir = dirs::home_dir()
-            .ok_or_else(|| anyhow::anyhow!("Could not find home directory"))?;
-        let exe_path = home_dir
-            .join("AppData")
-            .join("Local")
-            .join("Programs")
-            .join("Antigravity")
-            .join("Antigravity.exe");
-        
-        if exe_path.exists() {
-            Command::new(&exe_path).spawn()?;
-        } else {
-            // Try to find it in Program Files
-            let program_files = PathBuf::from("C:\\Program Files\\Antigravity\\Antigravity.exe");
-            if program_files.exists() {
-                Command::new(&program_files).spawn()?;
-            } else {
-                anyhow::bail!("Could not find Antigravity.exe");
-            }
-        }
+        vec![vec!["taskkill", "/F", "/IM", "Antigravity.exe", "/T"]]
     }
+}
+
+/// Record how the main IDE process was started
+#[cfg(target_os = "linux")]
+fn launch_context(processes: &[process::IdeProcess]) -> Option<LaunchContext> {
+    processes
+        .iter()
+        .find(|p| p.is_main() && !p.cmdline.is_empty())
+        .map(|p| LaunchContext {
+            cmdline: p.cmdline.clone(),
+            cwd: p.cwd.clone(),
+        })
+}
+
+/// Find the IDE processes that use the target's user-data dir
+#[cfg(target_os = "linux")]
+fn target_processes(target: &Installation) -> Vec<process::IdeProcess> {
+    let target_dir = fs::canonicalize(&target.user_data_dir).unwrap_or_else(|_| target.user_data_dir.clone());
     
-    #[cfg(target_os = "linux")]
-    {
-        let _ = Command::new("antigravity").spawn();
+    process::find_ide_processes()
+        .into_iter()
+        .filter(|p| match p.user_data_dir() {
+            Some(dir) => fs::canonicalize(&dir).unwrap_or(dir) == target_dir,
+            // Without the flag the IDE uses its default dir, which only un-named targets use
+            None => target.profile.is_none(),
+        })
+        .collect()
+}
+
+/// Restart Antigravity, reusing the previous command line and working directory when known
+///
+/// The IDE is started detached from our process group. Returns whether it
+/// was seen running before the launch timeout.
+pub async fn restart_antigravity(target: &Installation, previous: Option<&LaunchContext>) -> anyhow::Result<bool> {
+    let command_line = launch_command(target, previous)?;
+    let (program, args) = command_line
+        .split_first()
+        .ok_or_else(|| anyhow::anyhow!("No launcher found for {}", target.label))?;
+    
+    let mut command = Command::new(program);
+    command
+        .args(args)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null());
+    
+    // The folder the IDE was opened from may be relative to its working directory
+    if let Some(cwd) = previous.and_then(|p| p.cwd.as_ref()).filter(|dir| dir.is_dir()) {
+        command.current_dir(cwd);
     }
     
-    Ok(())
+    detach(&mut command);
+    let mut child = command.spawn()?;
+    let started = wait_for_launch(target, &mut child).await;
+    
+    // Reap the launcher whenever it exits so it does not linger as a zombie
+    std::thread::spawn(move || {
+        let _ = child.wait();
+    });
+    
+    Ok(started)
 }
 
-/// Get the Antigravity database path
-fn get_db_path() -> anyhow::Result<PathBuf> {
-    let home_dir = dirs::home_dir()
-        .ok_or_else(|| anyhow::anyhow!("Could not find home directory"))?;
+/// Build the command used to relaunch the IDE
+///
+/// The recorded command line is reused as-is when its program still exists.
+/// AppImage mounts and Flatpak sandbox paths are gone once the IDE exits, so
+/// in that case the target's launcher is used with the recorded arguments.
+fn launch_command(target: &Installation, previous: Option<&LaunchContext>) -> anyhow::Result<Vec<String>> {
+    let cmdline = match previous {
+        Some(previous) if !previous.cmdline.is_empty() => &previous.cmdline,
+        _ => return Ok(target.launcher.clone()),
+    };
+    let (program, args) = (&cmdline[0], &cmdline[1..]);
     
-    #[cfg(target_os = "macos")]
-    {
-        Ok(home_dir
-            .join("Library")
-            .join("Application Support")
-            .join("Antigravity")
-            .join("User")
-            .join("globalStorage")
-            .join("state.vscdb"))
+    if Path::new(program).is_file() {
+        return Ok(cmdline.clone());
     }
     
-    #[cfg(target_os = "windows")]
+    if target.launcher.is_empty() {
+        anyhow::bail!("No launcher found for {} and {} no longer exists", target.label, program);
+    }
+    
+    let mut command_line = target.launcher.clone();
+    command_line.extend(args.iter().filter(|arg| !target.launcher.contains(arg)).cloned());
+    Ok(command_line)
+}
+
+/// Start the command in its own process group so it outlives us and our signals
+fn detach(command: &mut Command) {
+    #[cfg(unix)]
     {
-        Ok(home_dir
-            .join("AppData")
-            .join("Roaming")
-            .join("Antigravity")
-            .join("User")
-            .join("globalStorage")
-            .join("state.vscdb"))
+        use std::os::unix::process::CommandExt;
+        command.process_group(0);
     }
     
-    #[cfg(target_os = "linux")]
+    #[cfg(windows)]
     {
-        Ok(home_dir
-            .join(".config")
-            .join("Antigravity")
-            .join("User")
-            .join("globalStorage")
-            .join("state.vscdb"))
+        use std::os::windows::process::CommandExt;
+        const DETACHED_PROCESS: u32 = 0x0000_0008;
+        const CREATE_NEW_PROCESS_GROUP: u32 = 0x0000_0200;
+        command.creation_flags(DETACHED_PROCESS | CREATE_NEW_PROCESS_GROUP);
     }
 }
 
-/// Clean lock files
-pub fn clean_lock_files() {
-    if let Ok(db_path) = get_db_path() {
-        for suffix in [".vscdb-wal", ".vscdb-shm"] {
-            let lock_path = db_path.to_string_lossy().replace(".vscdb", suffix);
-            let _ = fs::remove_file(&lock_path);
+/// Wait until the IDE is running, giving up early if the launcher fails
+async fn wait_for_launch(target: &Installation, child: &mut Child) -> bool {
+    let timeouts = settings::load_settings().map(|s| s.process).unwrap_or_default();
+    let deadline = tokio::time::Instant::now() + timeouts.launch_timeout();
+    
+    loop {
+        if is_target_running(target) {
+            return true;
+        }
+        
+        // Launchers like `open -a` exit immediately, so only a failed exit is conclusive
+        if let Ok(Some(status)) = child.try_wait() {
+            if !status.success() {
+                eprintln!("Warning: Antigravity launcher exited with {}", status);
+                return false;
+            }
+        }
+        
+        if tokio::time::Instant::now() >= deadline {
+            return false;
         }
+        tokio::time::sleep(LAUNCH_POLL_INTERVAL).await;
     }
 }
 
-/// Inject token into Antigravity database - FIXED VERSION
-pub async fn inject_token_into_db(
-    access_token: &str,
-    refresh_token: &str,
-    expiry: i64,
-    email: &str,
-) -> anyhow::Result<()> {
-    let db_path = get_db_path()?;
-    
-    if !db_path.exists() {
-        anyhow::bail!("Antigravity database not found at {:?}", db_path);
+/// Check whether the target's IDE is running
+pub fn is_target_running(target: &Installation) -> bool {
+    #[cfg(target_os = "linux")]
+    {
+        !target_processes(target).is_empty()
     }
     
-    // Make database writable (Unix only)
-    #[cfg(unix)]
+    #[cfg(not(target_os = "linux"))]
     {
-        use std::os::unix::fs::PermissionsExt;
-        let _ = fs::set_permissions(&db_path, std::fs::Permissions::from_mode(0o644));
+        let _ = target;
+        process::is_ide_running()
     }
+}
+
+/// What a switch would do, computed without changing anything
+#[derive(Debug, Clone, Serialize)]
+pub struct SwitchPlan {
+    pub target: String,
+    pub version_check: VersionCheck,
+    /// Name of the switch recipe that decides which keys are written and cleared
+    pub recipe: String,
+    pub db_path: PathBuf,
+    pub db_exists: bool,
+    /// Whether the access token would be refreshed before injecting
+    pub token_refresh_needed: bool,
+    pub signals: Vec<PlannedSignal>,
+    /// Command the IDE would be relaunched with, if it would be
+    pub relaunch: Option<Vec<String>>,
+    pub writes: Vec<PlannedWrite>,
+    /// OAuth fields as decoded back from the value that would be written, tokens masked
+    pub oauth: Option<OAuthFields>,
+    /// OAuth fields currently in the database, tokens masked
+    pub current_oauth: Option<OAuthFields>,
+    pub backups: Vec<PlannedBackup>,
+    /// Reasons the real switch would be refused, and what the plan cannot know yet
+    pub warnings: Vec<String>,
+}
+
+/// Signals a process would receive
+#[derive(Debug, Clone, Serialize)]
+pub struct PlannedSignal {
+    /// Unknown where every instance is killed by name
+    pub pid: Option<u32>,
+    pub command: Vec<String>,
+    pub signals: Vec<String>,
+}
+
+/// A database key that would be written or deleted
+#[derive(Debug, Clone, Serialize)]
+pub struct PlannedWrite {
+    pub key: String,
+    /// "insert", "update", "delete" or "restore" (from the account's snapshot)
+    pub action: String,
+    pub before_size: Option<usize>,
+    pub after_size: Option<usize>,
+}
+
+/// A file that would be copied into the backup directory
+#[derive(Debug, Clone, Serialize)]
+pub struct PlannedBackup {
+    pub source: PathBuf,
+    pub destination: PathBuf,
+    pub condition: String,
+}
+
+/// Work out what a switch would do to the target without touching it
+///
+/// `stop` and `launch` say whether the IDE would be stopped and relaunched.
+pub fn plan_switch(
+    target: &Installation,
+    recipe: &SwitchRecipe,
+    stop: bool,
+    launch: bool,
+    credentials: Credentials<'_>,
+) -> anyhow::Result<SwitchPlan> {
+    let db_path = target.db_path();
+    let db_exists = db_path.exists();
     
-    // Try to use sqlite3 command line tool
-    let jetski_key = "jetskiStateSync.agentManagerInitState";
-    let auth_key = "antigravityAuthStatus";
+    #[cfg(target_os = "linux")]
+    let (signals, previous) = if stop {
+        let processes = target_processes(target);
+        let signals = processes
+            .iter()
+            .map(|p| PlannedSignal {
+                pid: Some(p.pid),
+                command: p.cmdline.clone(),
+                signals: vec!["TERM".to_string(), "KILL if still running".to_string()],
+            })
+            .collect();
+        (signals, launch_context(&processes))
+    } else {
+        (Vec::new(), None)
+    };
     
-    // Create OAuth field data (simplified protobuf-like encoding)
-    let oauth_data = create_oauth_field(access_token, refresh_token, expiry);
-    let oauth_base64 = base64::encode(&oauth_data);
+    #[cfg(not(target_os = "linux"))]
+    let (signals, previous) = if stop {
+        let signals = kill_commands()
+            .into_iter()
+            .map(|command_line| PlannedSignal {
+                pid: None,
+                command: command_line.iter().map(|arg| arg.to_string()).collect(),
+                signals: vec!["KILL".to_string()],
+            })
+            .collect();
+        (signals, None)
+    } else {
+        (Vec::new(), None)
+    };
     
-    // Create auth status JSON
-    let auth_json = serde_json::json!({
-        "email": email,
-        "apiKey": access_token,
-        "name": email.split('@').next().unwrap_or("User")
-    });
+    let relaunch = if launch {
+        Some(launch_command(target, previous.as_ref())?)
+    } else {
+        None
+    };
     
-    // Try to update using sqlite3 CLI
-    let update_result = update_db_with_sqlite3(&db_path, jetski_key, &oauth_base64, auth_key, &auth_json.to_string()).await;
+    let values = encode_values(recipe, credentials);
+    
+    // Decode both the value that would be written and the one it replaces
+    let oauth_key = recipe
+        .writes
+        .iter()
+        .find(|write| write.encoding == ValueEncoding::OauthToken)
+        .map(|write| write.key.as_str());
+    let oauth = match oauth_key.and_then(|key| values.iter().find(|(k, _)| k == key)) {
+        Some((_, value)) => Some(mask_oauth(codec::decode_oauth_value(value)?)),
+        None => None,
+    };
+    let current_oauth = match oauth_key {
+        Some(key) if db_exists => read_value(&db_path, key)?
+            .and_then(|value| codec::decode_oauth_value(&value).ok())
+            .map(mask_oauth),
+        _ => None,
+    };
+    let existing = if db_exists { existing_value_sizes(&db_path, recipe)? } else { Vec::new() };
+    let size_of = |key: &str| existing.iter().find(|(k, _)| k == key).map(|(_, size)| *size);
     
-    if update_result.is_err() {
-        // Fallback: try direct file manipulation
-        anyhow::bail!("Database update failed");
+    let mut writes = Vec::new();
+    for (key, value) in &values {
+        let before_size = size_of(key);
+        writes.push(PlannedWrite {
+            key: key.clone(),
+            action: if before_size.is_some() { "update" } else { "insert" }.to_string(),
+            before_size,
+            after_size: Some(value.len()),
+        });
+    }
+    for (key, size) in &existing {
+        if !values.iter().any(|(written, _)| written == key) {
+            writes.push(PlannedWrite {
+                key: key.clone(),
+                action: "delete".to_string(),
+                before_size: Some(*size),
+                after_size: None,
+            });
+        }
     }
     
-    Ok(())
+    let mut backups = Vec::new();
+    if db_exists {
+        let backup_path = new_backup_path(&get_backup_dir(target)?);
+        let condition = "Always, before the WAL checkpoint; used by undo".to_string();
+        backups.push(PlannedBackup {
+            source: db_path.clone(),
+            destination: backup_path.clone(),
+            condition: condition.clone(),
+        });
+        for (lock_path, backup_lock_path) in get_lock_paths(&db_path).into_iter().zip(get_lock_paths(&backup_path)) {
+            if lock_path.exists() {
+                backups.push(PlannedBackup {
+                    source: lock_path,
+                    destination: backup_lock_path,
+                    condition: condition.clone(),
+                });
+            }
+        }
+    }
+    
+    Ok(SwitchPlan {
+        target: target.label.clone(),
+        version_check: check_version(target.version.as_deref(), Some(recipe)),
+        recipe: recipe.name.clone(),
+        db_path,
+        db_exists,
+        token_refresh_needed: false,
+        signals,
+        relaunch,
+        writes,
+        oauth,
+        current_oauth,
+        backups,
+        warnings: Vec::new(),
+    })
 }
 
-/// Update database using sqlite3 CLI
-async fn update_db_with_sqlite3(
-    db_path: &PathBuf,
-    jetski_key: &str,
-    oauth_value: &str,
-    auth_key: &str,
-    auth_value: &str,
-) -> anyhow::Result<()> {
-    // Check if jetski key exists
-    let check_output = Command::new("sqlite3")
-        .arg(db_path)
-        .arg(format!("SELECT value FROM ItemTable WHERE key = '{}';", jetski_key))
-        .output()?;
-    
-    let existing_value = String::from_utf8_lossy(&check_output.stdout);
-    
-    if existing_value.trim().is_empty() {
-        // Insert new record
-        let _ = Command::new("sqlite3")
-            .arg(db_path)
-            .arg(format!(
-                "INSERT INTO ItemTable (key, value) VALUES ('{}', '{}');",
-                jetski_key, oauth_value
-            ))
-            .output()?;
-    } else {
-        // Update existing record
-        let _ = Command::new("sqlite3")
-            .arg(db_path)
-            .arg(format!(
-                "UPDATE ItemTable SET value = '{}' WHERE key = '{}';",
-                oauth_value, jetski_key
-            ))
-            .output()?;
-    }
+/// Sizes of the current values of every key a switch writes or deletes
+fn existing_value_sizes(db_path: &PathBuf, recipe: &SwitchRecipe) -> anyhow::Result<Vec<(String, usize)>> {
+    let written: Vec<String> = recipe.writes.iter().map(|write| db::quote(&write.key)).collect();
+    
+    let output = db::execute_query(
+        db_path,
+        &format!(
+            "SELECT key, length(value) FROM ItemTable WHERE key IN ({}) OR ({});",
+            written.join(", "),
+            recipe.clear_condition()
+        ),
+    )?;
+    
+    Ok(output
+        .lines()
+        .filter_map(|line| line.rsplit_once('|'))
+        .map(|(key, size)| (key.to_string(), size.trim().parse().unwrap_or(0)))
+        .collect())
+}
+
+/// Read a key's current value as text, if it exists
+fn read_value(db_path: &PathBuf, key: &str) -> anyhow::Result<Option<String>> {
+    let output = db::execute_query(
+        db_path,
+        &format!("SELECT value FROM ItemTable WHERE key = {};", db::quote(key)),
+    )?;
+    let value = output.trim();
+    Ok((!value.is_empty()).then(|| value.to_string()))
+}
+
+/// Mask the tokens in decoded OAuth fields
+fn mask_oauth(mut fields: OAuthFields) -> OAuthFields {
+    fields.access_token = mask_secret(&fields.access_token);
+    fields.refresh_token = mask_secret(&fields.refresh_token);
+    fields
+}
+
+/// Show only the start of a secret, with its length
+fn mask_secret(secret: &str) -> String {
+    let prefix: String = secret.chars().take(8).collect();
+    format!("{}... ({} chars)", prefix, secret.chars().count())
+}
+
+/// Get the WAL and shared-memory files that accompany the database
+fn get_lock_paths(db_path: &Path) -> Vec<PathBuf> {
+    [".vscdb-wal", ".vscdb-shm"]
+        .iter()
+        .map(|suffix| PathBuf::from(db_path.to_string_lossy().replace(".vscdb", suffix)))
+        .collect()
+}
+
+/// Fail if any process still has the database or its WAL/SHM files open
+fn ensure_db_closed(db_path: &Path) -> anyhow::Result<()> {
+    let lock_paths = get_lock_paths(db_path);
     
-    // Update auth status
-    let _ = Command::new("sqlite3")
-        .arg(db_path)
-        .arg(format!(
-            "INSERT OR REPLACE INTO ItemTable (key, value) VALUES ('{}', '{}');",
-            auth_key, auth_value
-        ))
-        .output()?;
-    
-    // Clear cache keys
-    let cache_keys = [
-        "google.geminicodeassist",
-        "google.geminicodeassist.hasRunOnce",
-        "geminiCodeAssist.chatThreads",
-    ];
-    
-    for key in &cache_keys {
-        let _ = Command::new("sqlite3")
-            .arg(db_path)
-            .arg(format!(
-                "DELETE FROM ItemTable WHERE key = '{}' OR key LIKE '{}.%';",
-                key, key
-            ))
-            .output()?;
+    let mut paths: Vec<&Path> = vec![db_path];
+    paths.extend(lock_paths.iter().map(|p| p.as_path()));
+    if process::is_file_in_use(&paths) {
+        anyhow::bail!("Antigravity database is still open by another process");
     }
     
     Ok(())
 }
 
-/// Create OAuth field in protobuf-like format
-fn create_oauth_field(access_token: &str, refresh_token: &str, expiry: i64) -> Vec<u8> {
-    let mut parts = Vec::new();
+/// Back up the database, then fold the WAL into it so no committed change is lost before writing
+///
+/// Returns the backup, taken before anything is touched, which is the exact
+/// pre-switch state for undo; `None` if there is no database yet or the copy
+/// failed. Deleting the WAL is only a last resort when the checkpoint fails,
+/// and only after a backup was taken.
+pub fn prepare_database(target: &Installation) -> anyhow::Result<Option<PathBuf>> {
+    let db_path = target.db_path();
+    ensure_db_closed(&db_path)?;
     
-    // Field 1: access_token
-    add_string_field(&mut parts, 1, access_token);
+    if !db_path.exists() {
+        return Ok(None);
+    }
     
-    // Field 2: token_type (Bearer)
-    add_string_field(&mut parts, 2, "Bearer");
+    let backup_path = match backup_database(target) {
+        Ok(path) => Some(path),
+        Err(e) => {
+            eprintln!("Warning: Failed to back up the database, the switch cannot be undone: {}", e);
+            None
+        }
+    };
     
-    // Field 3: refresh_token
-    add_string_field(&mut parts, 3, refresh_token);
+    let checkpoint = db::execute_query(&db_path, "PRAGMA wal_checkpoint(TRUNCATE);");
     
-    // Field 4: expiry timestamp
-    let mut timestamp_parts = Vec::new();
-    add_varint(&mut timestamp_parts, (1 << 3) as u64); // Field 1, wire type 0
-    add_varint(&mut timestamp_parts, expiry as u64);
+    // The result row is "busy|log|checkpointed"; busy = 1 means it could not complete
+    let completed = matches!(&checkpoint, Ok(row) if row.trim().starts_with("0|"));
+    if completed {
+        return Ok(backup_path);
+    }
     
-    add_varint(&mut parts, ((4 << 3) | 2) as u64); // Field 4, wire type 2 (length-delimited)
-    add_varint(&mut parts, timestamp_parts.len() as u64);
-    parts.extend_from_slice(&timestamp_parts);
+    let backup_path = match backup_path {
+        Some(path) => path,
+        None => anyhow::bail!("WAL checkpoint failed and the database could not be backed up"),
+    };
+    eprintln!(
+        "Warning: WAL checkpoint failed ({}), removing WAL/SHM files after backing up to {:?}",
+        checkpoint.err().map(|e| e.to_string()).unwrap_or_else(|| "database busy".to_string()),
+        backup_path
+    );
     
-    // Wrap in field 6
-    let mut result = Vec::new();
-    add_varint(&mut result, ((6 << 3) | 2) as u64); // Field 6, wire type 2
-    add_varint(&mut result, parts.len() as u64);
-    result.extend_from_slice(&parts);
+    for lock_path in get_lock_paths(&db_path) {
+        let _ = fs::remove_file(lock_path);
+    }
     
-    result
+    Ok(Some(backup_path))
 }
 
-/// Add a string field to protobuf
-fn add_string_field(parts: &mut Vec<u8>, field_num: u32, value: &str) {
-    add_varint(parts, ((field_num << 3) | 2) as u64); // Wire type 2 = length-delimited
-    add_varint(parts, value.len() as u64);
-    parts.extend_from_slice(value.as_bytes());
+/// Get the directory where a target's database backups are kept
+fn get_backup_dir(target: &Installation) -> anyhow::Result<PathBuf> {
+    Ok(account::get_data_dir()?.join("backups").join(target.slug()))
 }
 
-/// Encode a value as varint
-fn add_varint(parts: &mut Vec<u8>, mut value: u64) {
-    while value >= 0x80 {
-        parts.push(((value & 0x7F) | 0x80) as u8);
-        value >>= 7;
+/// Copy the database and its WAL/SHM files into the backup directory
+///
+/// Returns the path of the database copy; the WAL/SHM copies sit next to it
+/// with the same suffixes. Only the most recent backups are kept.
+pub fn backup_database(target: &Installation) -> anyhow::Result<PathBuf> {
+    let db_path = target.db_path();
+    let backup_dir = get_backup_dir(target)?;
+    fs::create_dir_all(&backup_dir)?;
+    
+    let backup_path = new_backup_path(&backup_dir);
+    fs::copy(&db_path, &backup_path)?;
+    
+    for (lock_path, backup_lock_path) in get_lock_paths(&db_path).iter().zip(get_lock_paths(&backup_path)) {
+        if lock_path.exists() {
+            fs::copy(lock_path, backup_lock_path)?;
+        }
     }
-    parts.push(value as u8);
+    
+    prune_backups(&backup_dir);
+    Ok(backup_path)
 }
 
-// Base64 encoding module
-mod base64 {
-    const ALPHABET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+/// Put a backup taken by `backup_database` back in place of the target's database
+///
+/// The database must be closed. The current database is backed up first, so a
+/// restore can itself be undone by hand.
+pub fn restore_database(target: &Installation, backup_path: &Path) -> anyhow::Result<()> {
+    if !backup_path.exists() {
+        anyhow::bail!("Backup {:?} no longer exists", backup_path);
+    }
     
-    pub fn encode(data: &[u8]) -> String {
-        let mut result = String::new();
-        let mut i = 0;
-        
-        while i < data.len() {
-            let b1 = data[i];
-            let b2 = if i + 1 < data.len() { data[i + 1] } else { 0 };
-            let b3 = if i + 2 < data.len() { data[i + 2] } else { 0 };
-            
-            let idx1 = (b1 >> 2) as usize;
-            let idx2 = (((b1 & 0x03) << 4) | (b2 >> 4)) as usize;
-            let idx3 = (((b2 & 0x0F) << 2) | (b3 >> 6)) as usize;
-            let idx4 = (b3 & 0x3F) as usize;
-            
-            result.push(ALPHABET[idx1] as char);
-            result.push(ALPHABET[idx2] as char);
-            
-            if i + 1 < data.len() {
-                result.push(ALPHABET[idx3] as char);
-            } else {
-                result.push('=');
-            }
-            
-            if i + 2 < data.len() {
-                result.push(ALPHABET[idx4] as char);
-            } else {
-                result.push('=');
-            }
-            
-            i += 3;
+    let db_path = target.db_path();
+    ensure_db_closed(&db_path)?;
+    if db_path.exists() {
+        backup_database(target)?;
+    }
+    
+    for lock_path in get_lock_paths(&db_path) {
+        let _ = fs::remove_file(lock_path);
+    }
+    fs::copy(backup_path, &db_path)?;
+    for (backup_lock_path, lock_path) in get_lock_paths(backup_path).iter().zip(get_lock_paths(&db_path)) {
+        if backup_lock_path.exists() {
+            fs::copy(backup_lock_path, lock_path)?;
         }
-        
-        result
     }
+    
+    Ok(())
+}
+
+/// Timestamped path for a new database backup
+fn new_backup_path(backup_dir: &Path) -> PathBuf {
+    let stamp = chrono::Utc::now().format("%Y%m%d-%H%M%S%.3f");
+    backup_dir.join(format!("state-{}.vscdb", stamp))
+}
+
+/// Remove all but the most recent backups
+fn prune_backups(backup_dir: &Path) {
+    let mut backups: Vec<PathBuf> = match fs::read_dir(backup_dir) {
+        Ok(entries) => entries
+            .flatten()
+            .map(|e| e.path())
+            .filter(|p| p.extension().map(|ext| ext == "vscdb").unwrap_or(false))
+            .collect(),
+        Err(_) => return,
+    };
+    
+    // Timestamped names sort chronologically
+    backups.sort();
+    let excess = backups.len().saturating_sub(MAX_BACKUPS);
+    for old in &backups[..excess] {
+        let _ = fs::remove_file(old);
+        for lock_path in get_lock_paths(old) {
+            let _ = fs::remove_file(lock_path);
+        }
+    }
+}
+
+/// Inject token into Antigravity database - FIXED VERSION
+pub async fn inject_token_into_db(
+    target: &Installation,
+    recipe: &SwitchRecipe,
+    credentials: Credentials<'_>,
+) -> anyhow::Result<()> {
+    let db_path = target.db_path();
+    
+    if !db_path.exists() {
+        anyhow::bail!("Antigravity database not found at {:?}", db_path);
+    }
+    
+    // Make database writable (Unix only)
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let _ = fs::set_permissions(&db_path, std::fs::Permissions::from_mode(0o644));
+    }
+    
+    let values = encode_values(recipe, credentials);
+    
+    // Try to update using sqlite3 CLI
+    if let Err(e) = update_db_with_sqlite3(&db_path, recipe, &values).await {
+        anyhow::bail!("Database update failed: {}", e);
+    }
+    
+    Ok(())
+}
+
+/// Build the value of every key the recipe writes
+fn encode_values(recipe: &SwitchRecipe, credentials: Credentials<'_>) -> Vec<(String, String)> {
+    recipe
+        .writes
+        .iter()
+        .map(|write| {
+            let value = match write.encoding {
+                ValueEncoding::OauthToken => codec::encode_oauth_value(
+                    credentials.access_token,
+                    credentials.refresh_token,
+                    credentials.expiry,
+                ),
+                ValueEncoding::AuthStatus => auth_status_json(credentials.access_token, credentials.email),
+            };
+            (write.key.clone(), value)
+        })
+        .collect()
+}
+
+/// Update database using sqlite3 CLI
+///
+/// Clears the recipe's cache keys and writes the new values in one transaction.
+async fn update_db_with_sqlite3(
+    db_path: &PathBuf,
+    recipe: &SwitchRecipe,
+    values: &[(String, String)],
+) -> anyhow::Result<()> {
+    let mut script = String::from("BEGIN;\n");
+    script.push_str(&format!("DELETE FROM ItemTable WHERE {};\n", recipe.clear_condition()));
+    for (key, value) in values {
+        script.push_str(&format!(
+            "INSERT OR REPLACE INTO ItemTable (key, value) VALUES ({}, {});\n",
+            db::quote(key),
+            db::quote(value)
+        ));
+    }
+    script.push_str("COMMIT;\n");
+    
+    db::execute_script(db_path, &script)?;
+    Ok(())
+}
+
+/// Build the auth status JSON (email, access token and name)
+fn auth_status_json(access_token: &str, email: &str) -> String {
+    serde_json::json!({
+        "email": email,
+        "apiKey": access_token,
+        "name": email.split('@').next().unwrap_or("User")
+    })
+    .to_string()
 }
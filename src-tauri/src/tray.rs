@@ -0,0 +1,132 @@
+// System tray - account list with quota and one-click switching
+
+use tauri::{AppHandle, CustomMenuItem, Manager, SystemTrayMenu, SystemTrayMenuItem};
+
+use crate::account::Account;
+use crate::audit::Trigger;
+use crate::quota::QuotaInfo;
+use crate::settings;
+use crate::AppState;
+
+/// Event that rebuilds the tray menu and reloads the frontend's account list
+pub const ACCOUNTS_UPDATED: &str = "accounts-updated";
+
+/// Menu item ids of accounts are this prefix followed by the account id
+const SWITCH_PREFIX: &str = "switch:";
+
+/// Build the tray menu for the current accounts and their last known quota
+///
+/// The check mark is on the account active in the target the tray switches.
+pub fn build_menu(app: &AppHandle) -> SystemTrayMenu {
+    let target = switch_target();
+    let state = app.state::<AppState>();
+    let accounts = {
+        let manager = state.account_manager.lock().unwrap();
+        manager.load_accounts().unwrap_or_default()
+    };
+    let quotas = state.quota_cache.lock().unwrap();
+
+    let mut menu = SystemTrayMenu::new();
+    for account in &accounts {
+        let mut item = CustomMenuItem::new(
+            format!("{}{}", SWITCH_PREFIX, account.id),
+            account_title(account, quotas.get(&account.id)),
+        );
+        if account.is_active_in(target.as_deref()) {
+            item = item.selected();
+        }
+        menu = menu.add_item(item);
+    }
+    if accounts.is_empty() {
+        menu = menu.add_item(CustomMenuItem::new("no_accounts", "No accounts").disabled());
+    }
+
+    menu.add_native_item(SystemTrayMenuItem::Separator)
+        .add_item(CustomMenuItem::new("refresh", "Refresh quotas"))
+        .add_item(CustomMenuItem::new("add", "Add account…"))
+        .add_native_item(SystemTrayMenuItem::Separator)
+        .add_item(CustomMenuItem::new("show", "Show"))
+        .add_item(CustomMenuItem::new("quit", "Quit"))
+}
+
+/// Menu label: the email with the primary model's remaining quota
+fn account_title(account: &Account, quota: Option<&QuotaInfo>) -> String {
+    if account.auth_revoked {
+        return format!("{} (sign-in required)", account.email);
+    }
+
+    // Models are sorted by catalog priority, so the first one is the primary model
+    match quota.and_then(|q| q.models.first()) {
+        Some(model) => format!("{} ({} {}%)", account.email, model.display_name, model.percentage),
+        None => account.email.clone(),
+    }
+}
+
+/// The named target the tray and shortcuts switch, or `None` for the default IDE
+pub fn switch_target() -> Option<String> {
+    settings::load_settings().ok().and_then(|s| s.tray_target)
+}
+
+/// Replace the tray menu with one built from the current accounts
+pub fn rebuild(app: &AppHandle) {
+    if let Err(e) = app.tray_handle().set_menu(build_menu(app)) {
+        eprintln!("Warning: Failed to update tray menu: {}", e);
+    }
+}
+
+/// Tell the frontend and the tray that accounts or their quotas changed
+pub fn emit_accounts_updated(app: &AppHandle) {
+    let _ = app.emit_all(ACCOUNTS_UPDATED, ());
+    // emit_all only reaches the webview; Rust listeners need a global trigger
+    app.trigger_global(ACCOUNTS_UPDATED, None);
+}
+
+/// Rebuild the tray menu whenever accounts are updated
+///
+/// Every change to accounts goes through this event; quota fetches rebuild
+/// the menu directly, so the account list is not reloaded each time.
+pub fn listen(app: &AppHandle) {
+    let handle = app.clone();
+    app.listen_global(ACCOUNTS_UPDATED, move |_| rebuild(&handle));
+}
+
+/// Handle a click on a tray menu item
+pub fn handle_menu_click(app: &AppHandle, id: &str) {
+    if let Some(account_id) = id.strip_prefix(SWITCH_PREFIX) {
+        crate::spawn_switch(app, account_id.to_string(), Trigger::Tray);
+        return;
+    }
+
+    match id {
+        "refresh" => refresh_quotas(app),
+        "add" => {
+            show_main_window(app);
+            let _ = app.emit_all("tray-add-account", ());
+        }
+        "show" => show_main_window(app),
+        "quit" => std::process::exit(0),
+        _ => {}
+    }
+}
+
+/// Bring the main window to the front
+pub fn show_main_window(app: &AppHandle) {
+    if let Some(window) = app.get_window("main") {
+        let _ = window.show();
+        let _ = window.set_focus();
+    }
+}
+
+/// Re-fetch every account's quota, then rebuild the menu
+fn refresh_quotas(app: &AppHandle) {
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        let state = app.state::<AppState>();
+        let accounts = {
+            let manager = state.account_manager.lock().unwrap();
+            manager.load_accounts().unwrap_or_default()
+        };
+        crate::collect_quotas(&app, accounts, Trigger::Tray).await;
+        emit_accounts_updated(&app);
+    });
+}
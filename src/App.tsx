@@ -24,14 +24,12 @@ function AccountsView({
   accounts,
   isRefreshing,
   onRefresh,
-  onAddAccount,
-  onUpdate
+  onAddAccount
 }: {
   accounts: Account[];
   isRefreshing: boolean;
   onRefresh: () => void;
   onAddAccount: () => void;
-  onUpdate: () => void;
 }) {
   const activeAccount = accounts.find(a => a.is_active);
   const lastUpdated = activeAccount?.last_checked
@@ -62,7 +60,6 @@ function AccountsView({
               <AccountCard
                 key={account.id}
                 account={account}
-                onUpdate={onUpdate}
               />
             ))}
           </div>
@@ -91,8 +88,21 @@ function AppContent() {
       loadAccounts();
     });
 
+    // "Add account…" in the tray menu
+    const unlistenTrayAdd = listen('tray-add-account', () => {
+      setActiveView('accounts');
+      setIsAddModalOpen(true);
+    });
+
+    // "Show switcher" global shortcut
+    const unlistenSwitcher = listen('show-switcher', () => {
+      setActiveView('accounts');
+    });
+
     return () => {
       unlisten.then(f => f());
+      unlistenTrayAdd.then(f => f());
+      unlistenSwitcher.then(f => f());
     };
     // eslint-disable-next-line react-hooks/exhaustive-deps
   }, []);
@@ -177,8 +187,6 @@ function AppContent() {
           `Imported ${result.added} accounts, updated ${result.updated}`,
           'success'
         );
-
-        await loadAccounts();
       }
     } catch (error) {
       showToast('Failed to import accounts', 'error');
@@ -202,7 +210,6 @@ function AppContent() {
       if (response.success && response.account) {
         console.log('OAuth successful, account:', response.account);
         showToast(`Successfully added account: ${response.account.email}`, 'success');
-        await loadAccounts();
       } else {
         console.log('OAuth failed:', response.error);
         // Use the actual error message from the backend
@@ -238,7 +245,6 @@ function AppContent() {
             isRefreshing={isRefreshing}
             onRefresh={handleRefresh}
             onAddAccount={() => setIsAddModalOpen(true)}
-            onUpdate={loadAccounts}
           />
         );
       case 'settings':
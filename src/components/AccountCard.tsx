@@ -4,14 +4,14 @@ import { useState } from 'react';
 import { Check, Loader2, Trash2, AlertTriangle } from 'lucide-react';
 import type { Account } from '@/types';
 import { useToast } from '@/hooks/use-toast';
-import { switchAccount, deleteAccount } from '@/lib/tauri-api';
+import { switchAccount, deleteAccount, revertLastSwitch } from '@/lib/tauri-api';
 
 interface AccountCardProps {
   account: Account;
-  onUpdate: () => void;
 }
 
-export function AccountCard({ account, onUpdate }: AccountCardProps) {
+// The list reloads on the backend's accounts-updated event, so actions here don't reload it
+export function AccountCard({ account }: AccountCardProps) {
   const [isSwitching, setIsSwitching] = useState(false);
   const [isDeleting, setIsDeleting] = useState(false);
   const { showToast } = useToast();
@@ -24,9 +24,11 @@ export function AccountCard({ account, onUpdate }: AccountCardProps) {
 
     setIsSwitching(true);
     try {
-      const email = await switchAccount(account.id);
-      showToast(`Switched to ${email}`, 'success');
-      onUpdate();
+      const { email, warning } = await switchAccount(account.id);
+      showToast(`Switched to ${email}`, 'success', { label: 'Undo', onClick: handleUndo });
+      if (warning) {
+        showToast(warning, 'info');
+      }
     } catch (error) {
       showToast(error instanceof Error ? error.message : 'Switch failed', 'error');
     } finally {
@@ -34,6 +36,15 @@ export function AccountCard({ account, onUpdate }: AccountCardProps) {
     }
   };
 
+  const handleUndo = async () => {
+    try {
+      const email = await revertLastSwitch();
+      showToast(`Switched back to ${email}`, 'success');
+    } catch (error) {
+      showToast(error instanceof Error ? error.message : 'Undo failed', 'error');
+    }
+  };
+
   const handleDelete = async () => {
     if (isDeleting) return;
 
@@ -44,7 +55,6 @@ export function AccountCard({ account, onUpdate }: AccountCardProps) {
     try {
       await deleteAccount(account.id);
       showToast('Account deleted', 'success');
-      onUpdate();
     } catch {
       showToast('Failed to delete account', 'error');
       setIsDeleting(false);
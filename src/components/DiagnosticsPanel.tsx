@@ -0,0 +1,129 @@
+// Diagnostics Panel Component - environment and IDE compatibility report
+
+import { useState, useEffect, useCallback } from 'react';
+import { writeText } from '@tauri-apps/api/clipboard';
+import { Copy, Loader2, RefreshCw } from 'lucide-react';
+import { useToast } from '@/hooks/use-toast';
+import { getDiagnostics } from '@/lib/tauri-api';
+import type { Diagnostics, VersionStatus } from '@/types';
+
+const STATUS_LABELS: Record<VersionStatus, string> = {
+  supported: 'Supported',
+  unknown: 'Version unknown',
+  unsupported: 'Unsupported',
+};
+
+export function DiagnosticsPanel() {
+  const [report, setReport] = useState<Diagnostics | null>(null);
+  const [isLoading, setIsLoading] = useState(false);
+  const { showToast } = useToast();
+
+  const loadReport = useCallback(async () => {
+    setIsLoading(true);
+    try {
+      setReport(await getDiagnostics());
+    } catch (error) {
+      showToast(`Failed to collect diagnostics: ${error}`, 'error');
+    } finally {
+      setIsLoading(false);
+    }
+  }, [showToast]);
+
+  useEffect(() => {
+    loadReport();
+  }, [loadReport]);
+
+  // The raw report is what bug reports need, so it is copied as JSON
+  const handleCopy = async () => {
+    if (!report) return;
+    try {
+      await writeText(JSON.stringify(report, null, 2));
+      showToast('Diagnostics copied to clipboard', 'success');
+    } catch {
+      showToast('Failed to copy diagnostics', 'error');
+    }
+  };
+
+  return (
+    <div className="swift-card p-5 space-y-4">
+      <div className="flex items-center justify-between">
+        <h3 className="text-sm font-semibold text-[var(--text-primary)]">
+          Diagnostics
+        </h3>
+        <div className="flex items-center gap-2">
+          <button
+            onClick={loadReport}
+            disabled={isLoading}
+            className="btn-secondary flex items-center gap-2"
+            title="Collect the report again"
+          >
+            {isLoading ? <Loader2 className="w-4 h-4 animate-spin" /> : <RefreshCw className="w-4 h-4" />}
+            Refresh
+          </button>
+          <button
+            onClick={handleCopy}
+            disabled={!report}
+            className="btn-secondary flex items-center gap-2"
+          >
+            <Copy className="w-4 h-4" />
+            Copy
+          </button>
+        </div>
+      </div>
+
+      {report && (
+        <>
+          <div className="space-y-1">
+            <Row label="App version" value={report.app_version} />
+            <Row label="System" value={`${report.os} (${report.arch})`} />
+            <Row label="SQLite" value={report.sqlite3_version ?? 'Not found'} />
+            <Row label="Data directory" value={report.data_dir ?? 'Not available'} mono />
+          </div>
+
+          {report.installations.length === 0 ? (
+            <p className="text-[12px] text-[var(--text-tertiary)]">
+              No Antigravity installation was found.
+            </p>
+          ) : (
+            <div className="space-y-3">
+              {report.installations.map(({ installation, running, recipe, version_check }) => (
+                <div
+                  key={installation.id}
+                  className="px-4 py-3 rounded-xl bg-[var(--surface-tertiary)] space-y-1"
+                >
+                  <div className="flex items-center justify-between">
+                    <span className="text-sm font-medium text-[var(--text-primary)]">
+                      {installation.label}
+                      {report.selected === installation.id && ' (selected)'}
+                    </span>
+                    <span className="text-[12px] text-[var(--text-secondary)]">
+                      {running ? 'Running' : 'Not running'}
+                    </span>
+                  </div>
+                  <Row
+                    label="Version"
+                    value={`${version_check.version ?? 'unknown'} - ${STATUS_LABELS[version_check.status]} (${version_check.supported_range})`}
+                  />
+                  <Row label="Recipe" value={recipe ?? 'None'} />
+                  <Row label="Database" value={installation.db_exists ? 'Found' : 'Missing'} />
+                  {version_check.message && (
+                    <p className="text-[12px] text-[var(--text-tertiary)]">{version_check.message}</p>
+                  )}
+                </div>
+              ))}
+            </div>
+          )}
+        </>
+      )}
+    </div>
+  );
+}
+
+function Row({ label, value, mono }: { label: string; value: string; mono?: boolean }) {
+  return (
+    <div className="flex items-center justify-between gap-3 py-1">
+      <span className="text-sm text-[var(--text-secondary)]">{label}</span>
+      <span className={`text-sm text-[var(--text-primary)] truncate ${mono ? 'font-mono' : ''}`}>{value}</span>
+    </div>
+  );
+}
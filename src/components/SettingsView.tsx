@@ -1,12 +1,23 @@
 // Settings View Component - SwiftUI-inspired
 
 import { useState, useEffect } from 'react';
-import { FolderOpen } from 'lucide-react';
+import { listen } from '@tauri-apps/api/event';
+import { FolderOpen, Loader2 } from 'lucide-react';
 import { useToast } from '@/hooks/use-toast';
-import { getDataDir, openUrl } from '@/lib/tauri-api';
+import { DiagnosticsPanel } from '@/components/DiagnosticsPanel';
+import { getDataDir, getSettings, openUrl, updateSettings } from '@/lib/tauri-api';
+import type { Settings, SwitchMode } from '@/types';
+
+const SWITCH_MODES: { value: SwitchMode; label: string }[] = [
+  { value: 'inject_only', label: 'Only update the database' },
+  { value: 'restart_if_running', label: 'Restart Antigravity if running' },
+  { value: 'always_launch', label: 'Always launch Antigravity' },
+];
 
 export function SettingsView() {
   const [dataDir, setDataDir] = useState('');
+  const [settings, setSettings] = useState<Settings | null>(null);
+  const [isSaving, setIsSaving] = useState(false);
   const { showToast } = useToast();
 
   useEffect(() => {
@@ -21,6 +32,39 @@ export function SettingsView() {
     loadDataDir();
   }, []);
 
+  // Load settings on mount and follow changes saved elsewhere (e.g. another window)
+  useEffect(() => {
+    getSettings()
+      .then(setSettings)
+      .catch((error) => showToast(`Failed to load settings: ${error}`, 'error'));
+
+    const unlisten = listen<Settings>('settings-changed', (event) => {
+      setSettings(event.payload);
+    });
+    return () => {
+      unlisten.then(f => f());
+    };
+  }, [showToast]);
+
+  const update = <K extends keyof Settings>(section: K, value: Partial<Settings[K]>) => {
+    setSettings((current) =>
+      current ? { ...current, [section]: { ...(current[section] as object), ...value } } : current
+    );
+  };
+
+  const handleSave = async () => {
+    if (!settings) return;
+    setIsSaving(true);
+    try {
+      setSettings(await updateSettings(settings));
+      showToast('Settings saved', 'success');
+    } catch (error) {
+      showToast(`${error}`, 'error');
+    } finally {
+      setIsSaving(false);
+    }
+  };
+
   const handleOpenDataDir = async () => {
     try {
       // Open the data directory in file explorer
@@ -61,6 +105,200 @@ export function SettingsView() {
           </p>
         </div>
 
+        {settings && (
+          <>
+            {/* Switching */}
+            <div className="swift-card p-5 space-y-4">
+              <h3 className="text-sm font-semibold text-[var(--text-primary)]">
+                Switching
+              </h3>
+
+              <div className="space-y-2">
+                <label className="text-sm font-medium text-[var(--text-secondary)]">
+                  When switching accounts
+                </label>
+                <select
+                  value={settings.switch_mode}
+                  onChange={(e) => setSettings({ ...settings, switch_mode: e.target.value as SwitchMode })}
+                  className="swift-input w-full"
+                >
+                  {SWITCH_MODES.map((mode) => (
+                    <option key={mode.value} value={mode.value}>
+                      {mode.label}
+                    </option>
+                  ))}
+                </select>
+              </div>
+
+              {settings.targets.length > 0 && (
+                <div className="space-y-2">
+                  <label className="text-sm font-medium text-[var(--text-secondary)]">
+                    Tray and shortcuts switch
+                  </label>
+                  <select
+                    value={settings.tray_target ?? ''}
+                    onChange={(e) => setSettings({ ...settings, tray_target: e.target.value || null })}
+                    className="swift-input w-full"
+                  >
+                    <option value="">Default Antigravity</option>
+                    {settings.targets.map((target) => (
+                      <option key={target.name} value={target.name}>
+                        {target.name}
+                      </option>
+                    ))}
+                  </select>
+                </div>
+              )}
+
+              <div className="grid grid-cols-3 gap-3">
+                <NumberField
+                  label="Shutdown timeout (s)"
+                  value={settings.process.shutdown_timeout_secs}
+                  onChange={(value) => update('process', { shutdown_timeout_secs: value })}
+                />
+                <NumberField
+                  label="Kill timeout (s)"
+                  value={settings.process.kill_timeout_secs}
+                  onChange={(value) => update('process', { kill_timeout_secs: value })}
+                />
+                <NumberField
+                  label="Launch timeout (s)"
+                  value={settings.process.launch_timeout_secs}
+                  onChange={(value) => update('process', { launch_timeout_secs: value })}
+                />
+              </div>
+
+              <div className="grid grid-cols-2 gap-3">
+                <NumberField
+                  label="Warning below (%)"
+                  value={settings.quota_thresholds.default.warning}
+                  onChange={(value) =>
+                    update('quota_thresholds', {
+                      default: { ...settings.quota_thresholds.default, warning: value },
+                    })
+                  }
+                />
+                <NumberField
+                  label="Critical below (%)"
+                  value={settings.quota_thresholds.default.critical}
+                  onChange={(value) =>
+                    update('quota_thresholds', {
+                      default: { ...settings.quota_thresholds.default, critical: value },
+                    })
+                  }
+                />
+              </div>
+            </div>
+
+            {/* Sign-in */}
+            <div className="swift-card p-5 space-y-4">
+              <h3 className="text-sm font-semibold text-[var(--text-primary)]">
+                Sign-in
+              </h3>
+
+              <div className="grid grid-cols-2 gap-3">
+                <NumberField
+                  label="Callback port"
+                  value={settings.oauth.callback_port}
+                  onChange={(value) => update('oauth', { callback_port: value })}
+                />
+                <NumberField
+                  label="Timeout (min)"
+                  value={settings.oauth.timeout_minutes}
+                  onChange={(value) => update('oauth', { timeout_minutes: value })}
+                />
+              </div>
+
+              <p className="text-[12px] text-[var(--text-tertiary)]">
+                The port must be allowed as a redirect URI of the OAuth client.
+              </p>
+            </div>
+
+            {/* Notifications */}
+            <div className="swift-card p-5 space-y-3">
+              <h3 className="text-sm font-semibold text-[var(--text-primary)]">
+                Notifications
+              </h3>
+
+              <Toggle
+                label="Show notifications"
+                checked={settings.notifications.enabled}
+                onChange={(checked) => update('notifications', { enabled: checked })}
+              />
+              <Toggle
+                label="Switch results"
+                checked={settings.notifications.switch_result}
+                disabled={!settings.notifications.enabled}
+                onChange={(checked) => update('notifications', { switch_result: checked })}
+              />
+              <Toggle
+                label="Low quota"
+                checked={settings.notifications.low_quota}
+                disabled={!settings.notifications.enabled}
+                onChange={(checked) => update('notifications', { low_quota: checked })}
+              />
+              <Toggle
+                label="Quota reset"
+                checked={settings.notifications.quota_reset}
+                disabled={!settings.notifications.enabled}
+                onChange={(checked) => update('notifications', { quota_reset: checked })}
+              />
+              <Toggle
+                label="Sign-in required"
+                checked={settings.notifications.auth_revoked}
+                disabled={!settings.notifications.enabled}
+                onChange={(checked) => update('notifications', { auth_revoked: checked })}
+              />
+            </div>
+
+            {/* Shortcuts */}
+            <div className="swift-card p-5 space-y-3">
+              <h3 className="text-sm font-semibold text-[var(--text-primary)]">
+                Global Shortcuts
+              </h3>
+
+              <Toggle
+                label="Enable shortcuts"
+                checked={settings.shortcuts.enabled}
+                onChange={(checked) => update('shortcuts', { enabled: checked })}
+              />
+              <TextField
+                label="Next account"
+                value={settings.shortcuts.next_account ?? ''}
+                onChange={(value) => update('shortcuts', { next_account: value || null })}
+              />
+              <TextField
+                label="Best account"
+                value={settings.shortcuts.best_account ?? ''}
+                onChange={(value) => update('shortcuts', { best_account: value || null })}
+              />
+              <TextField
+                label="Show switcher"
+                value={settings.shortcuts.show_switcher ?? ''}
+                onChange={(value) => update('shortcuts', { show_switcher: value || null })}
+              />
+            </div>
+
+            <button
+              onClick={handleSave}
+              disabled={isSaving}
+              className="btn-primary w-full flex items-center justify-center gap-2"
+            >
+              {isSaving ? (
+                <>
+                  <Loader2 className="w-4 h-4 animate-spin" />
+                  Saving...
+                </>
+              ) : (
+                'Save Settings'
+              )}
+            </button>
+          </>
+        )}
+
+        {/* Diagnostics */}
+        <DiagnosticsPanel />
+
         {/* About Section */}
         <div className="swift-card p-5">
           <h3 className="text-sm font-semibold text-[var(--text-primary)] mb-4">
@@ -83,3 +321,73 @@ export function SettingsView() {
     </div>
   );
 }
+
+function NumberField({
+  label,
+  value,
+  onChange,
+}: {
+  label: string;
+  value: number;
+  onChange: (value: number) => void;
+}) {
+  return (
+    <div className="space-y-2">
+      <label className="text-[12px] font-medium text-[var(--text-secondary)]">{label}</label>
+      <input
+        type="number"
+        min={0}
+        value={value}
+        onChange={(e) => onChange(Number(e.target.value))}
+        className="swift-input w-full"
+      />
+    </div>
+  );
+}
+
+function TextField({
+  label,
+  value,
+  onChange,
+}: {
+  label: string;
+  value: string;
+  onChange: (value: string) => void;
+}) {
+  return (
+    <div className="flex items-center justify-between gap-3">
+      <span className="text-sm text-[var(--text-secondary)]">{label}</span>
+      <input
+        type="text"
+        value={value}
+        onChange={(e) => onChange(e.target.value)}
+        placeholder="Not set"
+        className="swift-input w-64 font-mono text-sm"
+      />
+    </div>
+  );
+}
+
+function Toggle({
+  label,
+  checked,
+  disabled,
+  onChange,
+}: {
+  label: string;
+  checked: boolean;
+  disabled?: boolean;
+  onChange: (checked: boolean) => void;
+}) {
+  return (
+    <label className="flex items-center justify-between py-1">
+      <span className="text-sm text-[var(--text-secondary)]">{label}</span>
+      <input
+        type="checkbox"
+        checked={checked}
+        disabled={disabled}
+        onChange={(e) => onChange(e.target.checked)}
+      />
+    </label>
+  );
+}
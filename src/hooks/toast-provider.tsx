@@ -1,11 +1,11 @@
 // Toast notification system
 
 import { createContext, useState, useCallback } from 'react';
-import type { Toast } from '@/types';
+import type { Toast, ToastAction } from '@/types';
 import { X, CheckCircle, AlertCircle, Info } from 'lucide-react';
 
 interface ToastContextType {
-    showToast: (message: string, type: Toast['type']) => void;
+    showToast: (message: string, type: Toast['type'], action?: ToastAction) => void;
 }
 
 export const ToastContext = createContext<ToastContextType | undefined>(undefined);
@@ -13,16 +13,16 @@ export const ToastContext = createContext<ToastContextType | undefined>(undefine
 export function ToastProvider({ children }: { children: React.ReactNode }) {
     const [toasts, setToasts] = useState<Toast[]>([]);
 
-    const showToast = useCallback((message: string, type: Toast['type']) => {
+    const showToast = useCallback((message: string, type: Toast['type'], action?: ToastAction) => {
         const id = Math.random().toString(36).substring(2, 9);
-        const newToast: Toast = { id, message, type };
+        const newToast: Toast = { id, message, type, action };
 
         setToasts((prev) => [...prev, newToast]);
 
-        // Auto remove after 3 seconds
+        // Auto remove after 3 seconds, leaving more time to click an action
         setTimeout(() => {
             setToasts((prev) => prev.filter((t) => t.id !== id));
-        }, 3000);
+        }, action ? 8000 : 3000);
     }, []);
 
     const removeToast = useCallback((id: string) => {
@@ -74,6 +74,17 @@ export function ToastProvider({ children }: { children: React.ReactNode }) {
                         <span className="text-sm font-medium text-[var(--text-primary)] flex-1">
                             {toast.message}
                         </span>
+                        {toast.action && (
+                            <button
+                                onClick={() => {
+                                    removeToast(toast.id);
+                                    toast.action?.onClick();
+                                }}
+                                className="px-2 py-1 rounded-lg text-sm font-semibold text-[var(--neon-lime)] hover:bg-white/5 transition-colors"
+                            >
+                                {toast.action.label}
+                            </button>
+                        )}
                         <button
                             onClick={() => removeToast(toast.id)}
                             className="p-1 rounded-lg hover:bg-white/5 transition-colors"
@@ -8,6 +8,24 @@ import type {
   SwitchResponse,
   ImportResponse,
   QuotaInfo,
+  AutoSwitchPolicy,
+  Recommendation,
+  RecommendResponse,
+  ModelCatalog,
+  QuotaThresholds,
+  Settings,
+  NotificationSettings,
+  ShortcutSettings,
+  InstallationsResponse,
+  InstallationSettings,
+  IdeTarget,
+  SwitchMode,
+  SwitchPlan,
+  SwitchRecipes,
+  SwitchOptions,
+  Diagnostics,
+  AuditEntry,
+  HistoryQuery,
 } from '@/types';
 
 // Get all accounts with quota
@@ -41,15 +59,43 @@ export async function deleteAccount(accountId: string): Promise<void> {
   await invoke('delete_account', { accountId });
 }
 
-// Switch to an account
-export async function switchAccount(accountId: string): Promise<string> {
-  const response = await invoke<SwitchResponse>('switch_account', { accountId });
+// Switch to an account, optionally in a named IDE target or with a specific switch mode
+export async function switchAccount(
+  accountId: string,
+  options: SwitchOptions = {}
+): Promise<{ email: string; warning?: string | null }> {
+  const response = await invoke<SwitchResponse>('switch_account', { accountId, ...options });
   if (response.success && response.email) {
-    return response.email;
+    return { email: response.email, warning: response.warning };
   }
   throw new Error(response.error || 'Failed to switch account');
 }
 
+// Undo the last switch, returning the email of the account switched back to
+export async function revertLastSwitch(): Promise<string> {
+  const response = await invoke<SwitchResponse>('revert_last_switch');
+  if (response.success && response.email) {
+    return response.email;
+  }
+  throw new Error(response.error || 'Failed to undo switch');
+}
+
+// Show what switching to an account would do, without changing anything
+export async function planSwitch(
+  accountId: string,
+  options: Omit<SwitchOptions, 'force'> = {}
+): Promise<SwitchPlan> {
+  const response = await invoke<SwitchResponse>('switch_account', {
+    accountId,
+    ...options,
+    dryRun: true,
+  });
+  if (response.success && response.plan) {
+    return response.plan;
+  }
+  throw new Error(response.error || 'Failed to plan switch');
+}
+
 // Get active account
 export async function getActiveAccount(): Promise<Account | null> {
   const response = await invoke<AccountResponse>('get_active_account');
@@ -74,6 +120,16 @@ export async function importAccounts(jsonData: string): Promise<ImportResponse>
   return await invoke<ImportResponse>('import_accounts', { jsonData });
 }
 
+// Get audit log entries, newest first
+export async function getHistory(query: HistoryQuery = {}): Promise<AuditEntry[]> {
+  return await invoke<AuditEntry[]>('get_history', { query });
+}
+
+// Export audit log entries as JSONL
+export async function exportHistory(query: HistoryQuery = {}): Promise<string> {
+  return await invoke<string>('export_history', { query });
+}
+
 // Get data directory
 export async function getDataDir(): Promise<string> {
   return await invoke<string>('get_data_dir');
@@ -102,3 +158,113 @@ export async function handleOAuthCallback(code: string): Promise<Account> {
 export async function refreshQuota(accountId: string): Promise<QuotaInfo | null> {
   return await invoke<QuotaInfo | null>('refresh_quota', { accountId });
 }
+
+// Rank accounts for a model, best first
+export async function recommendAccount(model: string): Promise<Recommendation[]> {
+  const response = await invoke<RecommendResponse>('recommend_account', { model });
+  return response.recommendations;
+}
+
+// Get the effective model catalog
+export async function getModelCatalog(): Promise<ModelCatalog> {
+  return await invoke<ModelCatalog>('get_model_catalog');
+}
+
+// Get all application settings
+export async function getSettings(): Promise<Settings> {
+  return await invoke<Settings>('get_settings');
+}
+
+// Validate and save all application settings
+export async function updateSettings(settings: Settings): Promise<Settings> {
+  return await invoke<Settings>('update_settings', { settings });
+}
+
+// Get auto-switch policy
+export async function getAutoSwitchPolicy(): Promise<AutoSwitchPolicy> {
+  return await invoke<AutoSwitchPolicy>('get_auto_switch_policy');
+}
+
+// Update auto-switch policy
+export async function updateAutoSwitchPolicy(policy: AutoSwitchPolicy): Promise<AutoSwitchPolicy> {
+  return await invoke<AutoSwitchPolicy>('update_auto_switch_policy', { policy });
+}
+
+// Get notification settings
+export async function getNotificationSettings(): Promise<NotificationSettings> {
+  return await invoke<NotificationSettings>('get_notification_settings');
+}
+
+// Update notification settings
+export async function updateNotificationSettings(
+  notifications: NotificationSettings
+): Promise<NotificationSettings> {
+  return await invoke<NotificationSettings>('update_notification_settings', { notifications });
+}
+
+// Get global shortcut settings
+export async function getShortcutSettings(): Promise<ShortcutSettings> {
+  return await invoke<ShortcutSettings>('get_shortcut_settings');
+}
+
+// Update global shortcut settings and register them again
+export async function updateShortcutSettings(shortcuts: ShortcutSettings): Promise<ShortcutSettings> {
+  return await invoke<ShortcutSettings>('update_shortcut_settings', { shortcuts });
+}
+
+// Get low-quota thresholds
+export async function getQuotaThresholds(): Promise<QuotaThresholds> {
+  return await invoke<QuotaThresholds>('get_quota_thresholds');
+}
+
+// Update low-quota thresholds
+export async function updateQuotaThresholds(thresholds: QuotaThresholds): Promise<QuotaThresholds> {
+  return await invoke<QuotaThresholds>('update_quota_thresholds', { thresholds });
+}
+
+// List detected Antigravity installations
+export async function getInstallations(): Promise<InstallationsResponse> {
+  return await invoke<InstallationsResponse>('get_installations');
+}
+
+// Get installation settings
+export async function getInstallationSettings(): Promise<InstallationSettings> {
+  return await invoke<InstallationSettings>('get_installation_settings');
+}
+
+// Update installation settings
+export async function updateInstallationSettings(
+  installation: InstallationSettings
+): Promise<InstallationSettings> {
+  return await invoke<InstallationSettings>('update_installation_settings', { installation });
+}
+
+// Get named IDE targets
+export async function getTargets(): Promise<IdeTarget[]> {
+  return await invoke<IdeTarget[]>('get_targets');
+}
+
+// Replace named IDE targets
+export async function updateTargets(targets: IdeTarget[]): Promise<IdeTarget[]> {
+  return await invoke<IdeTarget[]>('update_targets', { targets });
+}
+
+// Get the default switch mode
+export async function getSwitchMode(): Promise<SwitchMode> {
+  return await invoke<SwitchMode>('get_switch_mode');
+}
+
+// Update the default switch mode
+export async function updateSwitchMode(mode: SwitchMode): Promise<SwitchMode> {
+  return await invoke<SwitchMode>('update_switch_mode', { mode });
+}
+
+// Get the effective switch recipes
+export async function getSwitchRecipes(): Promise<SwitchRecipes> {
+  return await invoke<SwitchRecipes>('get_switch_recipes');
+}
+
+// Get the environment and IDE compatibility report
+export async function getDiagnostics(): Promise<Diagnostics> {
+  return await invoke<Diagnostics>('get_diagnostics');
+}
@@ -7,6 +7,7 @@ export interface Account {
   picture?: string;
   quota?: QuotaInfo;
   is_active: boolean;
+  active_targets?: string[];
   last_checked?: number;
   refresh_token?: string;
 }
@@ -19,15 +20,32 @@ export interface QuotaInfo {
 export interface ModelQuota {
   name: string;
   display_name: string;
+  family?: string;
+  color?: string;
   percentage: number;
-  reset_time?: string;
+  remaining_fraction: number;
+  reset_time?: string; // RFC 3339
+  resets_in_seconds?: number;
   badge?: Badge;
+  projected_exhaustion?: number;
+  will_reset_first?: boolean;
 }
 
 export interface Badge {
   text: string;
   color: string;
-  badge_type: string;
+  badge_type: 'warning' | 'critical' | 'exhausted';
+}
+
+export interface ThresholdLevels {
+  warning: number;
+  critical: number;
+}
+
+export interface QuotaThresholds {
+  default: ThresholdLevels;
+  per_model: Record<string, ThresholdLevels>;
+  per_account: Record<string, Record<string, ThresholdLevels>>;
 }
 
 export interface AccountsResponse {
@@ -44,6 +62,89 @@ export interface SwitchResponse {
   success: boolean;
   email?: string;
   error?: string;
+  error_code?: string | null;
+  ide_restarted?: boolean | null;
+  plan?: SwitchPlan | null;
+  warning?: string | null;
+}
+
+export interface SwitchOptions {
+  target?: string;
+  mode?: SwitchMode;
+  force?: boolean;
+  trigger?: Trigger;
+}
+
+export type AuditEvent =
+  | 'switch'
+  | 'auto_switch'
+  | 'revert'
+  | 'add'
+  | 'delete'
+  | 'import'
+  | 'export'
+  | 'token_refresh_failed';
+
+export type Trigger = 'ui' | 'cli' | 'tray' | 'shortcut' | 'auto';
+
+export interface AuditEntry {
+  timestamp: number;
+  event: AuditEvent;
+  account_email?: string | null;
+  trigger: Trigger;
+  duration_ms?: number | null;
+  outcome: 'success' | 'failure';
+  error_code?: string | null;
+  detail?: string | null;
+}
+
+export interface HistoryQuery {
+  event?: AuditEvent;
+  account_email?: string;
+  since?: number;
+  limit?: number;
+}
+
+export interface PlannedSignal {
+  pid?: number | null;
+  command: string[];
+  signals: string[];
+}
+
+export interface PlannedWrite {
+  key: string;
+  action: 'insert' | 'update' | 'delete' | 'restore';
+  before_size?: number | null;
+  after_size?: number | null;
+}
+
+export interface PlannedBackup {
+  source: string;
+  destination: string;
+  condition: string;
+}
+
+export interface OAuthFields {
+  access_token: string;
+  token_type: string;
+  refresh_token: string;
+  expiry?: number | null;
+}
+
+export interface SwitchPlan {
+  target: string;
+  version_check: VersionCheck;
+  recipe: string;
+  db_path: string;
+  db_exists: boolean;
+  token_refresh_needed: boolean;
+  signals: PlannedSignal[];
+  relaunch?: string[] | null;
+  writes: PlannedWrite[];
+  oauth?: OAuthFields | null;
+  current_oauth?: OAuthFields | null;
+  backups: PlannedBackup[];
+  warnings: string[];
 }
 
 export interface ImportResponse {
@@ -52,10 +153,190 @@ export interface ImportResponse {
   updated: number;
 }
 
+export interface QuietHours {
+  start_hour: number;
+  end_hour: number;
+}
+
+export interface AutoSwitchPolicy {
+  enabled: boolean;
+  model: string;
+  threshold_percent: number;
+  cooldown_minutes: number;
+  check_interval_minutes: number;
+  quiet_hours?: QuietHours | null;
+  notify: boolean;
+}
+
+export interface NotificationSettings {
+  enabled: boolean;
+  switch_result: boolean;
+  low_quota: boolean;
+  quota_reset: boolean;
+  auth_revoked: boolean;
+  min_interval_minutes: number;
+}
+
+export interface ShortcutSettings {
+  enabled: boolean;
+  next_account?: string | null;
+  best_account?: string | null;
+  show_switcher?: string | null;
+}
+
+export interface Recommendation {
+  account_id: string;
+  email: string;
+  rank: number;
+  score: number;
+  remaining_percentage?: number;
+  resets_in_seconds?: number;
+  is_active: boolean;
+  auth_revoked: boolean;
+  reasons: string[];
+}
+
+export interface RecommendResponse {
+  success: boolean;
+  model: string;
+  recommendations: Recommendation[];
+}
+
+export interface ModelEntry {
+  match_all: string[];
+  match_none: string[];
+  display_name?: string;
+  family?: string;
+  priority: number;
+  low_quota_threshold?: number;
+  color?: string;
+}
+
+export interface ModelCatalog {
+  version: number;
+  models: ModelEntry[];
+}
+
+export type InstallKind = 'native' | 'tarball' | 'app_image' | 'flatpak' | 'snap' | 'custom';
+
+export interface Installation {
+  id: string;
+  kind: InstallKind;
+  label: string;
+  user_data_dir: string;
+  launcher: string[];
+  db_exists: boolean;
+  version?: string | null;
+  profile?: string | null;
+}
+
+export interface InstallationsResponse {
+  success: boolean;
+  installations: Installation[];
+  active?: string;
+}
+
+export interface CustomInstallation {
+  id?: string;
+  label: string;
+  user_data_dir: string;
+  launcher: string[];
+}
+
+export interface InstallationSettings {
+  selected?: string | null;
+  custom: CustomInstallation[];
+}
+
+export type SwitchMode = 'inject_only' | 'restart_if_running' | 'always_launch';
+
+export interface IdeTarget {
+  name: string;
+  user_data_dir: string;
+  launcher: string[];
+}
+
+export interface OAuthSettings {
+  callback_port: number;
+  timeout_minutes: number;
+}
+
+export interface ProcessSettings {
+  shutdown_timeout_secs: number;
+  kill_timeout_secs: number;
+  launch_timeout_secs: number;
+}
+
+export interface Settings {
+  auto_switch: AutoSwitchPolicy;
+  quota_thresholds: QuotaThresholds;
+  installation: InstallationSettings;
+  targets: IdeTarget[];
+  tray_target?: string | null;
+  switch_mode: SwitchMode;
+  notifications: NotificationSettings;
+  shortcuts: ShortcutSettings;
+  oauth: OAuthSettings;
+  process: ProcessSettings;
+}
+
+export type ValueEncoding = 'oauth_token' | 'auth_status';
+
+export interface KeyWrite {
+  key: string;
+  encoding: ValueEncoding;
+}
+
+export interface SwitchRecipe {
+  name: string;
+  min_version?: string | null;
+  max_version?: string | null;
+  writes: KeyWrite[];
+  clear: string[];
+  keep: string[];
+}
+
+export interface SwitchRecipes {
+  version: number;
+  recipes: SwitchRecipe[];
+}
+
+export type VersionStatus = 'supported' | 'unknown' | 'unsupported';
+
+export interface VersionCheck {
+  version?: string | null;
+  status: VersionStatus;
+  supported_range: string;
+  message?: string | null;
+}
+
+export interface InstallationDiagnostics {
+  installation: Installation;
+  running: boolean;
+  recipe?: string | null;
+  version_check: VersionCheck;
+}
+
+export interface Diagnostics {
+  app_version: string;
+  os: string;
+  arch: string;
+  data_dir?: string | null;
+  sqlite3_version?: string | null;
+  selected?: string | null;
+  installations: InstallationDiagnostics[];
+}
+
+export interface ToastAction {
+  label: string;
+  onClick: () => void;
+}
+
 export interface Toast {
   id: string;
   message: string;
   type: 'success' | 'error' | 'info';
+  action?: ToastAction;
 }
 
 export type ViewMode = 'accounts' | 'settings' | 'about';